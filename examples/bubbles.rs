@@ -29,25 +29,14 @@ fn main() {
     let start = std::time::Instant::now();
     let pixels = render(
         &camera,
-        |ray| {
-            silver::rng::reseed(silver::util::vec3_to_u64(ray.direction));
-            silver::sample::sample(&scene, env, ray, cutoff)
-        },
+        |ray| silver::sample::sample(&scene, env, ray, cutoff),
         width,
         height,
         sample_per_pixel,
     );
     println!("{:?} elapsed", start.elapsed());
 
-    let img = image::ImageBuffer::from_fn(width as u32, height as u32, |x, y| {
-        let col = silver::util::linear_to_gamma(pixels[y as usize][x as usize], 2.2);
-        image::Rgb([
-            ((col.r().min(1.0) * 255.99).floor() as u8),
-            ((col.g().min(1.0) * 255.99).floor() as u8),
-            ((col.b().min(1.0) * 255.99).floor() as u8),
-        ])
-    });
-    img.save(img_path).unwrap();
+    pixels.save(img_path).unwrap();
 
     println!("done!");
 }
@@ -89,7 +78,8 @@ fn make_scene() -> Vec<(BasicShape, BasicMaterial<'static>)> {
                         rng.gen_range(0.0..0.9f64).powi(2),
                     )),
                     i if i < 99 => {
-                        BasicMaterial::Dielectric(Dielectric::new(rng.gen_range(0.9..1.2)))
+                        // Soap bubbles
+                        BasicMaterial::Dielectric(Dielectric::new(1.33).with_thin_walled(true))
                     }
                     _ => BasicMaterial::DiffuseLight(DiffuseLight::new(Vec3::new([1.0, 1.0, 1.0]))),
                 },
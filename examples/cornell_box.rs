@@ -3,12 +3,14 @@ mod env_map;
 use silver::camera::Camera;
 #[allow(unused_imports)]
 use silver::envs::dark_env as env;
+use silver::integrator::PathTracer;
+use silver::lights::Lights;
 use silver::materials::uv_map::UvMap;
 use silver::materials::{Basic as BasicMaterial, *};
-use silver::ray::Ray;
 use silver::render::render;
 use silver::resolvers::linear_search::LinearSearch as Resolver;
 use silver::shapes::{Basic as BasicShape, Sphere};
+use silver::tone_mapping::{Operator, ToneMapping};
 use silver::vec3::Vec3;
 
 fn main() {
@@ -27,24 +29,20 @@ fn main() {
         4.0,
     );
     let sample_per_pixel = 25 * 25;
-    let cutoff = 10;
+    let cutoff = 50;
     let objects = make_cornell_box();
     let scene = Resolver::new(objects.iter().map(|(s, m)| (s, m)));
 
     // let env = env_map::env_map("qwantani_4k.exr");
 
-    let pdf_gen = |p1, location| {
-        let p2 = silver::pdf::ShapePdf::new(location, &objects[2].0);
-        let p = silver::pdf::MixturePdf::new(&p1, &p2);
-        silver::pdf::Pdf::generate_with_value(&p)
-    };
+    let lights = Lights::from_objects(scene.objects());
+    let path_tracer = PathTracer::new(&lights).with_depth(3, cutoff);
 
     let start = std::time::Instant::now();
     let pixels = render(
         &camera,
         |ray| {
-            silver::rng::reseed(silver::util::vec3_to_u64(ray.direction));
-            silver::sample::sample_weighted(&scene, &env, ray, cutoff, &pdf_gen)
+            path_tracer.sample(&scene, env, ray)
             // silver::sample::sample_with_volume(
             //     &scene,
             //     env,
@@ -63,15 +61,10 @@ fn main() {
     );
     println!("{:?} elapsed", start.elapsed());
 
-    let img = image::ImageBuffer::from_fn(width as u32, height as u32, |x, y| {
-        let col = silver::util::linear_to_gamma(pixels[y as usize][x as usize], 2.2);
-        image::Rgb([
-            ((col.r().min(1.0) * 255.99).floor() as u8),
-            ((col.g().min(1.0) * 255.99).floor() as u8),
-            ((col.b().min(1.0) * 255.99).floor() as u8),
-        ])
-    });
-    img.save(img_path).unwrap();
+    pixels
+        .tone_map(&ToneMapping::new(Operator::AcesFilmic).with_exposure(0.5))
+        .save(img_path)
+        .unwrap();
 
     println!("done!");
 }
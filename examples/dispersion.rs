@@ -0,0 +1,63 @@
+use silver::camera::Camera;
+use silver::envs::dark_env as env;
+use silver::integrator::PathTracer;
+use silver::lights::Lights;
+use silver::materials::dielectric::Dispersion;
+use silver::materials::{Basic as BasicMaterial, *};
+use silver::render::render;
+use silver::resolvers::linear_search::LinearSearch as Resolver;
+use silver::shapes::{Basic as BasicShape, Sphere};
+use silver::vec3::Vec3;
+
+fn main() {
+    let img_path = "./dispersion.png";
+
+    let width = 480;
+    let height = 320;
+    let camera = Camera::new(
+        &Vec3::new([0.0, 0.3, 3.0]),
+        &Vec3::new([0.0, 0.3, 0.0]),
+        &Vec3::new([0.0, 1.0, 0.0]),
+        30.0f64.to_radians(),
+        width as f64 / height as f64,
+        0.0,
+        3.0,
+    );
+
+    // A diamond in front of a row of small lights, which it splits into spectra.
+    let mut objects = vec![
+        (
+            BasicShape::Sphere(Sphere::new(Vec3::new([0.0, 0.3, 0.0]), 0.4)),
+            BasicMaterial::Dielectric(Dielectric::new(2.42).with_dispersion(Dispersion::diamond())),
+        ),
+        (
+            BasicShape::Sphere(Sphere::new(Vec3::new([0.0, -1000.1, 0.0]), 1000.0)),
+            BasicMaterial::Lambertian(Lambertian::new(Vec3::new([0.5; 3]))),
+        ),
+    ];
+    for i in -4..=4 {
+        objects.push((
+            BasicShape::Sphere(Sphere::new(Vec3::new([i as f64 * 0.3, 0.6, -1.5]), 0.03)),
+            BasicMaterial::DiffuseLight(DiffuseLight::new(Vec3::new([30.0; 3]))),
+        ));
+    }
+    let scene = Resolver::new(objects.iter().map(|(s, m)| (s, m)));
+    let lights = Lights::from_objects(scene.objects());
+    let path_tracer = PathTracer::new(&lights)
+        .with_depth(3, 32)
+        .with_spectral(true);
+
+    let start = std::time::Instant::now();
+    let pixels = render(
+        &camera,
+        |ray| path_tracer.sample(&scene, env, ray),
+        width,
+        height,
+        256,
+    );
+    println!("{:?} elapsed", start.elapsed());
+
+    pixels.save(img_path).unwrap();
+
+    println!("done!");
+}
@@ -7,7 +7,7 @@ pub fn env_map(
     path: &str,
 ) -> (
     impl Fn(&Ray) -> Vec3,
-    impl Fn(silver::pdf::CosinePdf, Vec3) -> (Vec3, f64),
+    impl Fn(silver::pdf::Basic, Vec3) -> (Vec3, f64),
 ) {
     let img = image::io::Reader::open(path).unwrap().decode().unwrap();
     let img = img.to_rgb32f();
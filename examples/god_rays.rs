@@ -0,0 +1,97 @@
+use silver::camera::Camera;
+use silver::integrator::PathTracer;
+use silver::lights::Lights;
+use silver::materials::atmosphere::Atmosphere;
+use silver::materials::{Basic as BasicMaterial, *};
+use silver::phase::{self, HenyeyGreenstein};
+use silver::render::render;
+use silver::resolvers::linear_search::LinearSearch as Resolver;
+use silver::shapes::{Basic as BasicShape, Sphere, Triangle};
+use silver::tone_mapping::{Operator, ToneMapping};
+use silver::vec3::Vec3;
+
+/// Two triangles of a horizontal rectangle at `y`.
+fn rectangle(y: f64, [x0, z0]: [f64; 2], [x1, z1]: [f64; 2]) -> [BasicShape; 2] {
+    let corner = |x, z| Vec3::new([x, y, z]);
+    [
+        BasicShape::TriangleBothSide(Triangle::new(
+            corner(x0, z0),
+            corner(x1, z0),
+            corner(x1, z1),
+        )),
+        BasicShape::TriangleBothSide(Triangle::new(
+            corner(x0, z0),
+            corner(x1, z1),
+            corner(x0, z1),
+        )),
+    ]
+}
+
+fn main() {
+    let img_path = "./god_rays.png";
+
+    let width = 480;
+    let height = 320;
+    let camera = Camera::new(
+        &Vec3::new([0.0, 1.0, 5.0]),
+        &Vec3::new([0.0, 0.8, 0.0]),
+        &Vec3::new([0.0, 1.0, 0.0]),
+        60.0f64.to_radians(),
+        width as f64 / height as f64,
+        0.0,
+        5.0,
+    );
+
+    // A ceiling with a square opening, under a bright light: the fog below glows along the
+    // shaft of light coming through.
+    let ceiling = Lambertian::new(Vec3::new([0.5; 3]));
+    let (near, far, hole) = (-10.0, 10.0, 0.6);
+    let mut objects: Vec<_> = [
+        rectangle(2.5, [near, near], [far, -hole]),
+        rectangle(2.5, [near, hole], [far, far]),
+        rectangle(2.5, [near, -hole], [-hole, hole]),
+        rectangle(2.5, [hole, -hole], [far, hole]),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|shape| (shape, BasicMaterial::Lambertian(ceiling.clone())))
+    .collect();
+    objects.extend([
+        (
+            BasicShape::Sphere(Sphere::new(Vec3::new([1.5, 8.0, -1.0]), 1.5)),
+            BasicMaterial::DiffuseLight(DiffuseLight::new(Vec3::new([40.0; 3]))),
+        ),
+        (
+            BasicShape::Sphere(Sphere::new(Vec3::new([0.0, 0.5, -0.5]), 0.5)),
+            BasicMaterial::Lambertian(Lambertian::new(Vec3::new([0.7, 0.3, 0.2]))),
+        ),
+        (
+            BasicShape::Sphere(Sphere::new(Vec3::new([0.0, -1000.0, 0.0]), 1000.0)),
+            BasicMaterial::Lambertian(Lambertian::new(Vec3::new([0.5; 3]))),
+        ),
+    ]);
+    let scene = Resolver::new(objects.iter().map(|(s, m)| (s, m)));
+    let lights = Lights::from_objects(scene.objects());
+    let atmosphere = Atmosphere::new(0.15, Vec3::new([0.9; 3]))
+        .with_phase(phase::Basic::HenyeyGreenstein(HenyeyGreenstein::new(0.5)));
+    let path_tracer = PathTracer::new(&lights)
+        .with_depth(3, 32)
+        .with_atmosphere(Some(atmosphere));
+
+    let start = std::time::Instant::now();
+    let pixels = render(
+        &camera,
+        |ray| path_tracer.sample(&scene, |_| Vec3::ZERO, ray),
+        width,
+        height,
+        64,
+    );
+    println!("{:?} elapsed", start.elapsed());
+
+    pixels
+        .tone_map(&ToneMapping::new(Operator::AcesFilmic))
+        .save(img_path)
+        .unwrap();
+
+    println!("done!");
+}
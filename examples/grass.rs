@@ -1,8 +1,9 @@
 use rand::Rng;
 use silver::camera::Camera;
 use silver::envs::default_env as env;
+use silver::filter::Filter;
 use silver::materials::{Lambertian, Material};
-use silver::render::render;
+use silver::render::{CancelToken, TiledRenderer};
 use silver::resolvers::bvh::BVH as Resolver;
 use silver::shapes::edge::Edge;
 use silver::vec3::Vec3;
@@ -27,27 +28,22 @@ fn main() {
     let scene = Resolver::new(objects.iter().map(|(s, m)| (s, m)));
 
     let start = std::time::Instant::now();
-    let pixels = render(
-        &camera,
-        |ray| {
-            silver::rng::reseed(silver::util::vec3_to_u64(ray.direction));
-            silver::sample::sample(&scene, env, ray, cutoff)
-        },
-        width,
-        height,
-        sample_per_pixel,
-    );
+    // The blades are thinner than a pixel; a wider filter keeps them from aliasing.
+    let pixels = TiledRenderer::default()
+        .with_filter(Filter::Lanczos { radius: 2.0 })
+        .render(
+            &camera,
+            |ray| silver::sample::sample(&scene, env, ray, cutoff),
+            width,
+            height,
+            sample_per_pixel,
+            &(),
+            &CancelToken::new(),
+        )
+        .unwrap();
     println!("{:?} elapsed", start.elapsed());
 
-    let img = image::ImageBuffer::from_fn(width as u32, height as u32, |x, y| {
-        let col = silver::util::linear_to_gamma(pixels[y as usize][x as usize], 2.2);
-        image::Rgb([
-            ((col.r().min(1.0) * 255.99).floor() as u8),
-            ((col.g().min(1.0) * 255.99).floor() as u8),
-            ((col.b().min(1.0) * 255.99).floor() as u8),
-        ])
-    });
-    img.save(img_path).unwrap();
+    pixels.save(img_path).unwrap();
 
     println!("done!");
 }
@@ -21,7 +21,7 @@ fn main() {
         5.0,
     );
     let sample_per_pixel = 100;
-    let (faces, _) = silver::formats::obj::load("./niko256.obj");
+    let obj = silver::formats::obj::load("./niko256.obj").unwrap();
 
     let img = image::open("niko256_niko.png").unwrap();
 
@@ -36,24 +36,28 @@ fn main() {
             2.2,
         )
     });
-    let objects: Vec<_> = faces
+    let objects: Vec<_> = obj
+        .faces
         .into_iter()
         .map(|f| {
             (
                 // silver::shapes::Triangle::new(transform(f[0].0), transform(f[1].0), transform(f[2].0)),
                 silver::shapes::triangle_with_normals::TriangleWithNormals::<true>::new(
                     [
-                        transform(f.0[0].0),
-                        transform(f.0[1].0),
-                        transform(f.0[2].0),
+                        transform(f.vertexes[0].0),
+                        transform(f.vertexes[1].0),
+                        transform(f.vertexes[2].0),
                     ],
                     [
-                        transform(f.0[0].2),
-                        transform(f.0[1].2),
-                        transform(f.0[2].2),
+                        transform(f.vertexes[0].2),
+                        transform(f.vertexes[1].2),
+                        transform(f.vertexes[2].2),
                     ],
                 ),
-                silver::materials::tex::Tex::new(tex_pixel.clone(), [f.0[0].1, f.0[1].1, f.0[2].1]),
+                silver::materials::tex::Tex::new(
+                    tex_pixel.clone(),
+                    [f.vertexes[0].1, f.vertexes[1].1, f.vertexes[2].1],
+                ),
             )
         })
         .collect();
@@ -63,25 +67,14 @@ fn main() {
     let start = std::time::Instant::now();
     let pixels = render(
         &camera,
-        |ray| {
-            silver::rng::reseed(silver::util::vec3_to_u64(ray.direction));
-            silver::sample::sample(&scene, silver::envs::default_env, ray, 50)
-        },
+        |ray| silver::sample::sample(&scene, silver::envs::default_env, ray, 50),
         width,
         height,
         sample_per_pixel,
     );
     println!("{:?} elapsed", start.elapsed());
 
-    let img = image::ImageBuffer::from_fn(width as u32, height as u32, |x, y| {
-        let col = silver::util::linear_to_gamma(pixels[y as usize][x as usize], 2.2);
-        image::Rgb([
-            ((col.r().min(1.0) * 255.99).floor() as u8),
-            ((col.g().min(1.0) * 255.99).floor() as u8),
-            ((col.b().min(1.0) * 255.99).floor() as u8),
-        ])
-    });
-    img.save(img_path).unwrap();
+    pixels.save(img_path).unwrap();
 
     println!("done!");
 }
@@ -0,0 +1,34 @@
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
+
+use silver::render::Progressive;
+use silver::resolvers::linear_search::LinearSearch;
+
+fn main() {
+    let img_path = "./progressive.png";
+    let time_limit = Duration::from_secs(30);
+
+    let scene = silver::formats::yaml::load("./scene.yml").unwrap();
+    let objects = &scene.objects;
+    let resolver = LinearSearch::new(objects.iter().map(|(s, m)| (s, m)));
+
+    let mut progressive = Progressive::new(scene.width, scene.height);
+    let start = Instant::now();
+    progressive.run(
+        &scene.camera,
+        |ray| silver::sample::sample(&resolver, scene.env, ray, scene.cutoff),
+        4,
+        (scene.sample_per_pixel as usize).div_ceil(4),
+        |image, sample_per_pixel| {
+            println!("{} spp, {:?} elapsed", sample_per_pixel, start.elapsed());
+            image.save(img_path).unwrap();
+            if start.elapsed() > time_limit {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+    );
+
+    println!("done!");
+}
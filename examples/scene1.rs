@@ -34,7 +34,6 @@ fn main() {
     let pixels = render(
         &camera,
         |ray| {
-            silver::rng::reseed(silver::util::vec3_to_u64(ray.direction));
             // silver::sample::sample(&scene, silver::envs::fancy_env, ray, 20)
             // silver::sample::sample_weighted(&scene, silver::envs::fancy_env, ray, 20, &objects[0].0)
 
@@ -46,15 +45,7 @@ fn main() {
     );
     println!("{:?} elapsed", start.elapsed());
 
-    let img = image::ImageBuffer::from_fn(width as u32, height as u32, |x, y| {
-        let col = silver::util::linear_to_gamma(pixels[y as usize][x as usize], 2.2);
-        image::Rgb([
-            ((col.r().min(1.0) * 255.99).floor() as u8),
-            ((col.g().min(1.0) * 255.99).floor() as u8),
-            ((col.b().min(1.0) * 255.99).floor() as u8),
-        ])
-    });
-    img.save(img_path).unwrap();
+    pixels.save(img_path).unwrap();
 
     println!("done!");
 }
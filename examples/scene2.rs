@@ -34,7 +34,6 @@ fn main() {
     let pixels = render(
         &camera,
         |ray| {
-            silver::rng::reseed(silver::util::vec3_to_u64(ray.direction));
             // silver::sample::sample(&scene, &env, ray, 50)
             silver::sample::sample_weighted(&scene, &env, ray, 50, &pdf_gen)
         },
@@ -44,15 +43,7 @@ fn main() {
     );
     println!("{:?} elapsed", start.elapsed());
 
-    let img = image::ImageBuffer::from_fn(width as u32, height as u32, |x, y| {
-        let col = silver::util::linear_to_gamma(pixels[y as usize][x as usize], 2.2);
-        image::Rgb([
-            ((col.r().min(1.0) * 255.99).floor() as u8),
-            ((col.g().min(1.0) * 255.99).floor() as u8),
-            ((col.b().min(1.0) * 255.99).floor() as u8),
-        ])
-    });
-    img.save(img_path).unwrap();
+    pixels.save(img_path).unwrap();
 
     println!("done!");
 }
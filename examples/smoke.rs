@@ -0,0 +1,71 @@
+use silver::camera::Camera;
+use silver::integrator::PathTracer;
+use silver::lights::Lights;
+use silver::materials::heterogeneous_medium::{Density, HeterogeneousMedium, Noise};
+use silver::materials::{Basic as BasicMaterial, *};
+use silver::phase::{self, DoubleHenyeyGreenstein};
+use silver::render::render;
+use silver::resolvers::linear_search::LinearSearch as Resolver;
+use silver::shapes::{Basic as BasicShape, Sphere};
+use silver::tone_mapping::{Operator, ToneMapping};
+use silver::vec3::Vec3;
+
+fn main() {
+    let img_path = "./smoke.png";
+
+    let width = 480;
+    let height = 320;
+    let camera = Camera::new(
+        &Vec3::new([0.0, 0.8, 4.0]),
+        &Vec3::new([0.0, 0.6, 0.0]),
+        &Vec3::new([0.0, 1.0, 0.0]),
+        40.0f64.to_radians(),
+        width as f64 / height as f64,
+        0.0,
+        4.0,
+    );
+
+    // A puff of smoke lit from above, its density bound by a sphere, scattering mostly
+    // forward like cloud droplets.
+    let smoke = HeterogeneousMedium::new(
+        Density::Noise(Noise::new(10.0, 0.4).with_octaves(5).with_threshold(0.55)),
+        Vec3::new([0.9; 3]),
+    )
+    .with_phase(phase::Basic::DoubleHenyeyGreenstein(
+        DoubleHenyeyGreenstein::mie(),
+    ));
+    let objects = [
+        (
+            BasicShape::Sphere(Sphere::new(Vec3::new([0.0, 0.7, 0.0]), 0.7)),
+            BasicMaterial::HeterogeneousMedium(smoke),
+        ),
+        (
+            BasicShape::Sphere(Sphere::new(Vec3::new([0.0, -1000.0, 0.0]), 1000.0)),
+            BasicMaterial::Lambertian(Lambertian::new(Vec3::new([0.5; 3]))),
+        ),
+        (
+            BasicShape::Sphere(Sphere::new(Vec3::new([-2.0, 4.0, 2.0]), 1.5)),
+            BasicMaterial::DiffuseLight(DiffuseLight::new(Vec3::new([3.0; 3]))),
+        ),
+    ];
+    let scene = Resolver::new(objects.iter().map(|(s, m)| (s, m)));
+    let lights = Lights::from_objects(scene.objects());
+    let path_tracer = PathTracer::new(&lights).with_depth(3, 64);
+
+    let start = std::time::Instant::now();
+    let pixels = render(
+        &camera,
+        |ray| path_tracer.sample(&scene, |_| Vec3::new([0.1, 0.12, 0.15]), ray),
+        width,
+        height,
+        64,
+    );
+    println!("{:?} elapsed", start.elapsed());
+
+    pixels
+        .tone_map(&ToneMapping::new(Operator::AcesFilmic))
+        .save(img_path)
+        .unwrap();
+
+    println!("done!");
+}
@@ -1,48 +1,65 @@
-use silver::camera::Camera;
-use silver::render::render;
+use silver::denoise::Denoiser;
+use silver::integrator::PathTracer;
+use silver::lights::Lights;
+use silver::render::tile::{Report, Tile, TileOrder};
+use silver::render::{CancelToken, TiledRenderer};
 use silver::resolvers::linear_search::LinearSearch;
-use silver::vec3::Vec3;
 
 fn main() {
     let img_path = "./yaml.png";
+    let denoised_path = "./yaml_denoised.png";
+    // Written as yaml.albedo.exr, yaml.depth.exr...
+    let aov_path = "./yaml.exr";
 
-    let width = 640;
-    let height = 480;
-    let camera = Camera::new(
-        &Vec3::new([0.0, 1.0, 2.0]),
-        &Vec3::new([0.0, 0.8, 0.0]),
-        &Vec3::new([0.0, 1.0, 0.0]),
-        60.0f64.to_radians(),
-        width as f64 / height as f64,
-        0.01,
-        3.0,
-    );
-    let sample = 20;
-    let objects = silver::formats::yaml::load("./scene.yml").unwrap();
-    let scene = LinearSearch::new(objects.iter().map(|(s, m)| (s, m)));
+    let scene = silver::formats::yaml::load("./scene.yml").unwrap();
+    let (width, height) = (scene.width, scene.height);
+    let objects = &scene.objects;
+    let resolver = LinearSearch::new(objects.iter().map(|(s, m)| (s, m)));
+    let lights = Lights::from_objects(resolver.objects());
+    let path_tracer = PathTracer::new(&lights)
+        .with_depth(3, scene.cutoff as u32)
+        .with_spectral(scene.spectral)
+        .with_atmosphere(scene.atmosphere);
+    // Each object has its own material.
+    let material_id = |material: &_| {
+        objects
+            .iter()
+            .position(|(_, m)| std::ptr::eq(m, material))
+            .unwrap() as u32
+    };
 
     let start = std::time::Instant::now();
-    let pixels = render(
-        &camera,
-        |ray| {
-            silver::rng::reseed(silver::util::vec3_to_u64(ray.direction));
-            silver::sample::sample(&scene, silver::envs::default_env, ray, 50)
-        },
-        width,
-        height,
-        sample,
-    );
+    let progress = |_: &Tile, report: Report| {
+        if report.done.is_multiple_of(20) {
+            println!(
+                "{}/{} tiles, {:?} remaining",
+                report.done,
+                report.total,
+                report.eta().unwrap_or_default()
+            );
+        }
+    };
+    let (pixels, aovs) = TiledRenderer::new(16, TileOrder::Spiral)
+        .with_filter(scene.filter)
+        .with_adaptive(scene.adaptive)
+        .render_aovs(
+            &scene.camera,
+            |ray| path_tracer.sample_aov(&resolver, scene.env, ray, material_id),
+            width,
+            height,
+            scene.sample_per_pixel,
+            &progress,
+            &CancelToken::new(),
+        )
+        .unwrap();
     println!("{:?} elapsed", start.elapsed());
 
-    let img = image::ImageBuffer::from_fn(width as u32, height as u32, |x, y| {
-        let col = silver::util::linear_to_gamma(pixels[y as usize][x as usize], 2.2);
-        image::Rgb([
-            ((col.r().min(1.0) * 255.99).floor() as u8),
-            ((col.g().min(1.0) * 255.99).floor() as u8),
-            ((col.b().min(1.0) * 255.99).floor() as u8),
-        ])
-    });
-    img.save(img_path).unwrap();
+    pixels.save(img_path).unwrap();
+    aovs.save(aov_path).unwrap();
+    Denoiser::new()
+        .denoise(&pixels, &aovs)
+        .save(denoised_path)
+        .unwrap();
 
     println!("done!");
 }
@@ -37,7 +37,7 @@ impl Camera {
         if self.diaphragm == 0.0 {
             Ray::new(self.origin, self.w + self.u * u + self.v * v - self.origin)
         } else {
-            let (fu, fv) = random_vec2_in_unit_circle(rng);
+            let (fu, fv) = concentric_disk(rng.gen(), rng.gen());
             let origin =
                 self.origin + self.u * (fu * self.diaphragm) + self.v * (fv * self.diaphragm);
             Ray::new(origin, self.w + self.u * u + self.v * v - origin)
@@ -45,11 +45,19 @@ impl Camera {
     }
 }
 
-fn random_vec2_in_unit_circle(rng: &mut impl rand::Rng) -> (f64, f64) {
-    loop {
-        let (x, y) = (rng.gen::<f64>() * 2.0 - 1.0, rng.gen::<f64>() * 2.0 - 1.0);
-        if x * x + y * y < 1.0 {
-            return (x, y);
-        }
+/// Maps the unit square to the unit disk with Shirley's concentric mapping, which keeps
+/// stratified samples stratified.
+fn concentric_disk(u: f64, v: f64) -> (f64, f64) {
+    use std::f64::consts::FRAC_PI_4;
+
+    let (a, b) = (u * 2.0 - 1.0, v * 2.0 - 1.0);
+    if a == 0.0 && b == 0.0 {
+        (0.0, 0.0)
+    } else if a.abs() > b.abs() {
+        let theta = FRAC_PI_4 * (b / a);
+        (a * theta.cos(), a * theta.sin())
+    } else {
+        let theta = 2.0 * FRAC_PI_4 - FRAC_PI_4 * (a / b);
+        (b * theta.cos(), b * theta.sin())
     }
 }
@@ -0,0 +1,181 @@
+//! Edge-avoiding à-trous wavelet denoising (Dammertz et al., 2010)
+//!
+//! Blurs the image with a 5 x 5 B3 spline kernel whose taps spread twice as far on each
+//! pass, weighting the neighbours by how close their color, normal, depth and albedo are to
+//! the pixel's. The color is divided by the albedo before filtering and multiplied back
+//! after, so textures stay sharp.
+
+use rayon::prelude::*;
+
+use crate::{framebuffer::FrameBuffer, render::Aovs, tone_mapping::luminance, vec3::Vec3};
+
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Denoiser {
+    /// Passes of the kernel, 5 covering 125 pixels wide neighbourhoods.
+    pub iterations: u32,
+    /// Tolerated color difference, halved on each pass. Colors are compared after
+    /// compressing their luminance to [0, 1).
+    pub sigma_color: f64,
+    pub sigma_normal: f64,
+    /// Tolerated depth difference, relative to the depth.
+    pub sigma_depth: f64,
+    pub sigma_albedo: f64,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Denoiser {
+            iterations: 5,
+            sigma_color: 0.5,
+            sigma_normal: 0.3,
+            sigma_depth: 0.05,
+            sigma_albedo: 0.1,
+        }
+    }
+}
+
+impl Denoiser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_iterations(self, iterations: u32) -> Self {
+        Denoiser { iterations, ..self }
+    }
+
+    pub fn with_sigma_color(self, sigma_color: f64) -> Self {
+        Denoiser {
+            sigma_color,
+            ..self
+        }
+    }
+
+    /// Denoises `image` guided by the `albedo`, `normal` and `depth` of `aovs`, which must
+    /// have the same size.
+    pub fn denoise(&self, image: &FrameBuffer, aovs: &Aovs) -> FrameBuffer {
+        let (width, height) = (image.width(), image.height());
+        let albedo = aovs.albedo.pixels();
+        let normal = aovs.normal.pixels();
+        let depth = aovs.depth.pixels();
+
+        // Materials that reflect nothing, like lights, keep their color.
+        let demodulate = |a: f64| if a > 1e-3 { a } else { 1.0 };
+        let modulation: Vec<Vec3> = albedo
+            .iter()
+            .map(|a| Vec3::new([demodulate(a.r()), demodulate(a.g()), demodulate(a.b())]))
+            .collect();
+        let mut current: Vec<Vec3> = image
+            .pixels()
+            .iter()
+            .zip(&modulation)
+            .map(|(&c, &m)| Vec3::new([c.r() / m.r(), c.g() / m.g(), c.b() / m.b()]))
+            .collect();
+        let mut next = current.clone();
+
+        for iteration in 0..self.iterations {
+            let step = 1 << iteration;
+            let sigma_color = self.sigma_color * 0.5f64.powi(iteration as i32);
+            next.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+                for (x, out) in row.iter_mut().enumerate() {
+                    let p = x + y * width;
+                    let color = compress(current[p]);
+                    let mut sum = Vec3::ZERO;
+                    let mut weight_sum = 0.0;
+                    for (j, ky) in KERNEL.iter().enumerate() {
+                        let qy = y as isize + (j as isize - 2) * step;
+                        if qy < 0 || qy >= height as isize {
+                            continue;
+                        }
+                        for (i, kx) in KERNEL.iter().enumerate() {
+                            let qx = x as isize + (i as isize - 2) * step;
+                            if qx < 0 || qx >= width as isize {
+                                continue;
+                            }
+                            let q = qx as usize + qy as usize * width;
+                            let distance = |a: Vec3, b: Vec3| {
+                                let d = a - b;
+                                d.x() * d.x() + d.y() * d.y() + d.z() * d.z()
+                            };
+                            let w = kx
+                                * ky
+                                * (-distance(color, compress(current[q])) / sigma_color.powi(2))
+                                    .exp()
+                                * (-distance(normal[p], normal[q]) / self.sigma_normal.powi(2))
+                                    .exp()
+                                * (-distance(albedo[p], albedo[q]) / self.sigma_albedo.powi(2))
+                                    .exp()
+                                * self.depth_weight(depth[p].x(), depth[q].x());
+                            sum = sum + current[q] * w;
+                            weight_sum += w;
+                        }
+                    }
+                    // The center tap always has a weight of 9/64 at least.
+                    *out = sum / weight_sum;
+                }
+            });
+            std::mem::swap(&mut current, &mut next);
+        }
+
+        FrameBuffer::from_fn(width, height, |x, y| {
+            let i = x + y * width;
+            current[i] * modulation[i]
+        })
+    }
+
+    fn depth_weight(&self, a: f64, b: f64) -> f64 {
+        // Also true between two pixels of the background, at an infinite depth.
+        if a == b {
+            return 1.0;
+        }
+        (-(a - b).abs() / (self.sigma_depth * a.min(b))).exp()
+    }
+}
+
+/// Scales `color` down so that its luminance is in [0, 1), keeping fireflies from standing
+/// out of the color weights.
+fn compress(color: Vec3) -> Vec3 {
+    color / (1.0 + luminance(color).max(0.0))
+}
+
+#[test]
+fn test() {
+    use crate::render::Aov;
+
+    // Noise on two faces with different normals.
+    let (width, height) = (32, 32);
+    let face = |x: usize| if x < width / 2 { 0.2 } else { 0.8 };
+    let noise = |x: usize, y: usize| {
+        if (crate::samplers::mix_bits((x + y * width) as u64) & 1) == 0 {
+            0.1
+        } else {
+            -0.1
+        }
+    };
+    let image = FrameBuffer::from_fn(width, height, |x, y| Vec3::new([face(x) + noise(x, y); 3]));
+    let mut aovs = Aovs::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let normal = if x < width / 2 {
+                [1.0, 0.0, 0.0]
+            } else {
+                [0.0, 1.0, 0.0]
+            };
+            let aov = Aov {
+                albedo: Vec3::new([1.0; 3]),
+                normal: Vec3::new(normal),
+                depth: 1.0,
+                ..Aov::default()
+            };
+            aovs.set(x, y, &aov);
+        }
+    }
+
+    let denoised = Denoiser::new().denoise(&image, &aovs);
+    for y in 0..height {
+        for x in 0..width {
+            assert!((denoised.get(x, y).x() - face(x)).abs() < 0.03);
+        }
+    }
+}
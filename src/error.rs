@@ -0,0 +1,74 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Errors returned by the scene and asset loaders.
+#[derive(Debug)]
+pub enum Error {
+    /// The file could not be opened or read.
+    Io { path: PathBuf, source: io::Error },
+    /// The file contents are malformed. `line` and `column` are 1-based.
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The file contents are malformed, at no location the parser could tell.
+    Invalid { path: PathBuf, message: String },
+    /// `usemtl` refers to a material that no `mtllib` defined.
+    MissingMaterial {
+        path: PathBuf,
+        line: usize,
+        name: String,
+    },
+    /// An image could not be decoded or encoded.
+    Image { path: PathBuf, message: String },
+    /// A face refers to a vertex, texture coordinate or normal that does not exist.
+    BadIndex {
+        path: PathBuf,
+        line: usize,
+        index: i64,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(f, "{}:{}:{}: {}", path.display(), line, column, message),
+            Error::Invalid { path, message } => write!(f, "{}: {}", path.display(), message),
+            Error::MissingMaterial { path, line, name } => {
+                write!(
+                    f,
+                    "{}:{}: material `{}` is not defined",
+                    path.display(),
+                    line,
+                    name
+                )
+            }
+            Error::Image { path, message } => write!(f, "{}: {}", path.display(), message),
+            Error::BadIndex { path, line, index } => {
+                write!(
+                    f,
+                    "{}:{}: index {} is out of range",
+                    path.display(),
+                    line,
+                    index
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
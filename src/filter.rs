@@ -0,0 +1,139 @@
+//! Pixel reconstruction filters
+//!
+//! Distances are in pixels, measured from the pixel center. All filters are separable.
+
+use std::f64::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    /// Constant weight. A radius of 0.5 averages the samples inside each pixel.
+    Box { radius: f64 },
+    /// Weight falling linearly to 0 at `radius`.
+    Tent { radius: f64 },
+    /// `exp(-alpha * x^2)`, shifted down to reach 0 at `radius`.
+    Gaussian { radius: f64, alpha: f64 },
+    /// Mitchell-Netravali cubic. `b = c = 1/3` is the recommended compromise between
+    /// blurring and ringing.
+    Mitchell { radius: f64, b: f64, c: f64 },
+    /// Sinc windowed by a sinc stretched to `radius`.
+    Lanczos { radius: f64 },
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::Box { radius: 0.5 }
+    }
+}
+
+impl Filter {
+    pub fn radius(&self) -> f64 {
+        match *self {
+            Filter::Box { radius }
+            | Filter::Tent { radius }
+            | Filter::Gaussian { radius, .. }
+            | Filter::Mitchell { radius, .. }
+            | Filter::Lanczos { radius } => radius,
+        }
+    }
+
+    /// Weight of a sample at offset `[x, y]` from the pixel center. May be negative.
+    pub fn eval(&self, [x, y]: [f64; 2]) -> f64 {
+        self.eval_1d(x) * self.eval_1d(y)
+    }
+
+    fn eval_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        let radius = self.radius();
+        if x >= radius {
+            return 0.0;
+        }
+        match *self {
+            Filter::Box { .. } => 1.0,
+            Filter::Tent { .. } => radius - x,
+            Filter::Gaussian { alpha, .. } => {
+                (-alpha * x * x).exp() - (-alpha * radius * radius).exp()
+            }
+            Filter::Mitchell { b, c, .. } => {
+                let x = 2.0 * x / radius;
+                if x > 1.0 {
+                    ((-b - 6.0 * c) * x.powi(3)
+                        + (6.0 * b + 30.0 * c) * x.powi(2)
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c))
+                        / 6.0
+                } else {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x.powi(3)
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x.powi(2)
+                        + (6.0 - 2.0 * b))
+                        / 6.0
+                }
+            }
+            Filter::Lanczos { .. } => sinc(x) * sinc(x / radius),
+        }
+    }
+
+    /// Calls `f` with every pixel the sample at continuous image position `[px, py]`
+    /// contributes to, and the weight of the contribution.
+    /// Pixel `[x, y]` has its center at `[x, y]`.
+    pub fn splat(&self, [px, py]: [f64; 2], mut f: impl FnMut(i32, i32, f64)) {
+        let radius = self.radius();
+        let (x0, x1) = ((px - radius).ceil() as i32, (px + radius).floor() as i32);
+        let (y0, y1) = ((py - radius).ceil() as i32, (py + radius).floor() as i32);
+        for y in y0..=y1 {
+            let wy = self.eval_1d(y as f64 - py);
+            if wy == 0.0 {
+                continue;
+            }
+            for x in x0..=x1 {
+                let w = self.eval_1d(x as f64 - px) * wy;
+                if w != 0.0 {
+                    f(x, y, w);
+                }
+            }
+        }
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+#[test]
+fn test() {
+    let filters = [
+        Filter::default(),
+        Filter::Tent { radius: 1.0 },
+        Filter::Gaussian {
+            radius: 1.5,
+            alpha: 2.0,
+        },
+        Filter::Mitchell {
+            radius: 2.0,
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        },
+        Filter::Lanczos { radius: 3.0 },
+    ];
+    for filter in filters {
+        assert!(filter.eval([0.0, 0.0]) > 0.0);
+        assert_eq!(filter.eval([filter.radius(), 0.0]), 0.0);
+        assert_eq!(filter.eval([0.3, -0.2]), filter.eval([-0.3, 0.2]));
+
+        let mut pixels = vec![];
+        filter.splat([10.2, 5.0], |x, y, w| pixels.push((x, y, w)));
+        assert!(pixels
+            .iter()
+            .any(|&(x, y, w)| (x, y) == (10, 5) && (w - filter.eval([-0.2, 0.0])).abs() < 1e-9));
+        assert!(pixels.iter().all(|&(x, y, _)| {
+            (x as f64 - 10.2).abs() < filter.radius() && (y as f64 - 5.0).abs() < filter.radius()
+        }));
+    }
+
+    let mut pixels = vec![];
+    Filter::default().splat([3.0, 4.4], |x, y, w| pixels.push((x, y, w)));
+    assert_eq!(pixels, [(3, 4, 1.0)]);
+}
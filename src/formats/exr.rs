@@ -0,0 +1,92 @@
+//! OpenEXR writer. Writes a single part, uncompressed scanline image with 32-bit float
+//! R, G and B channels.
+
+use std::{io, path::Path};
+
+use crate::{framebuffer::FrameBuffer, Error};
+
+pub fn write(path: impl AsRef<Path>, image: &FrameBuffer) -> Result<(), Error> {
+    super::write_file(path.as_ref(), |w| write_to(w, image))
+}
+
+pub fn write_to(w: &mut impl io::Write, image: &FrameBuffer) -> io::Result<()> {
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    // Channels must be sorted by name.
+    let channels = ["B", "G", "R"];
+
+    let mut header = vec![];
+    // Magic number and version 2, single part scanline
+    header.extend([0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+
+    let mut chlist = vec![];
+    for name in channels {
+        chlist.extend(name.as_bytes());
+        chlist.push(0);
+        chlist.extend(2i32.to_le_bytes()); // FLOAT
+        chlist.extend([0, 0, 0, 0]); // pLinear and reserved
+        chlist.extend(1i32.to_le_bytes()); // xSampling
+        chlist.extend(1i32.to_le_bytes()); // ySampling
+    }
+    chlist.push(0);
+    attribute(&mut header, "channels", "chlist", &chlist);
+    attribute(&mut header, "compression", "compression", &[0]);
+    let window = [0, 0, width - 1, height - 1].map(i32::to_le_bytes).concat();
+    attribute(&mut header, "dataWindow", "box2i", &window);
+    attribute(&mut header, "displayWindow", "box2i", &window);
+    attribute(&mut header, "lineOrder", "lineOrder", &[0]); // INCREASING_Y
+    attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1f32.to_le_bytes(),
+    );
+    header.push(0);
+    w.write_all(&header)?;
+
+    // One scanline per chunk: y, data size and the channels one after another.
+    let line_size = image.width() * channels.len() * 4;
+    let chunk_size = 8 + line_size as u64;
+    let table_end = header.len() as u64 + 8 * image.height() as u64;
+    for y in 0..image.height() as u64 {
+        w.write_all(&(table_end + y * chunk_size).to_le_bytes())?;
+    }
+    for y in 0..image.height() {
+        w.write_all(&(y as i32).to_le_bytes())?;
+        w.write_all(&(line_size as i32).to_le_bytes())?;
+        for channel in [2, 1, 0] {
+            for x in 0..image.width() {
+                w.write_all(&(image.get(x, y)[channel] as f32).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, ty: &str, value: &[u8]) {
+    header.extend(name.as_bytes());
+    header.push(0);
+    header.extend(ty.as_bytes());
+    header.push(0);
+    header.extend((value.len() as i32).to_le_bytes());
+    header.extend(value);
+}
+
+#[test]
+fn test() {
+    use crate::vec3::Vec3;
+
+    let image = FrameBuffer::from_fn(3, 2, |x, y| Vec3::new([x as f64, y as f64, 10.5]));
+    let path = std::env::temp_dir().join(format!("silver_{}_exr_test.exr", std::process::id()));
+    write(&path, &image).unwrap();
+
+    let decoded = image::open(&path).unwrap().into_rgb32f();
+    assert_eq!((decoded.width(), decoded.height()), (3, 2));
+    assert_eq!(decoded.get_pixel(2, 1).0, [2.0, 1.0, 10.5]);
+}
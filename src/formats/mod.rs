@@ -1,2 +1,32 @@
+pub mod exr;
 pub mod obj;
+pub mod pfm;
+#[cfg(feature = "image")]
+pub mod png;
+pub mod ppm;
 pub mod yaml;
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::Error;
+
+/// Creates a file and lets `f` write to it.
+fn write_file(
+    path: &Path,
+    f: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> Result<(), Error> {
+    File::create(path)
+        .map(BufWriter::new)
+        .and_then(|mut writer| {
+            f(&mut writer)?;
+            writer.flush()
+        })
+        .map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })
+}
@@ -1,214 +1,626 @@
 use std::{
-    convert::TryInto,
+    fmt::Display,
     fs::File,
     io::{BufRead, BufReader},
-    path::Path,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
 };
 
-pub fn load(
-    obj_path: &str,
-) -> (
-    Vec<([([f32; 3], [f32; 2], [f32; 3]); 3], i32)>,
-    Vec<Material>,
-) {
-    let file = File::open(obj_path).unwrap();
-    let reader = BufReader::new(file);
+use crate::{materials, shapes, vec3::Vec3, Error};
+
+/// A triangle of an OBJ model. Polygons are fan-triangulated.
+pub struct Face {
+    /// Position, texture coordinate and normal of each vertex.
+    /// Missing texture coordinates are `[0.0, 0.0]` and missing normals are the flat face normal.
+    pub vertexes: [([f32; 3], [f32; 2], [f32; 3]); 3],
+    /// Index into `Obj::materials`, `None` before any `usemtl`.
+    pub material: Option<usize>,
+    /// Index into `Obj::objects`, `None` before any `o`.
+    pub object: Option<usize>,
+    /// Index into `Obj::groups`, `None` before any `g`.
+    pub group: Option<usize>,
+    /// `0` when smoothing is off.
+    pub smoothing_group: u32,
+}
+
+pub struct Obj {
+    pub faces: Vec<Face>,
+    pub materials: Vec<Material>,
+    pub objects: Vec<String>,
+    pub groups: Vec<String>,
+}
+
+pub fn load(obj_path: &str) -> Result<Obj, Error> {
+    let path = Path::new(obj_path);
+    let reader = open(path)?;
 
     let mut vs: Vec<[f32; 3]> = vec![];
     let mut vts: Vec<[f32; 2]> = vec![];
     let mut vns: Vec<[f32; 3]> = vec![];
-    let mut fs: Vec<([[i32; 3]; 3], i32)> = vec![];
-    let mut ms = vec![];
+    let mut obj = Obj {
+        faces: vec![],
+        materials: vec![],
+        objects: vec![],
+        groups: vec![],
+    };
 
-    let mut mtl = 0;
+    let mut material = None;
+    let mut object = None;
+    let mut group = None;
+    let mut smoothing_group = 0;
 
-    for line in reader.lines() {
-        let line = line.unwrap();
-        let mut tokens = line.split_whitespace();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        let mut tokens = Tokens::new(&line, path, i + 1);
         match tokens.next() {
             Some("mtllib") => {
-                let name = tokens.next().unwrap();
-                let mtl_path = Path::new(obj_path).parent().unwrap().join(name);
-                ms.extend(load_mtl(mtl_path));
-            }
-            Some("v") => {
-                vs.push(
-                    tokens
-                        .take(3)
-                        .map(|t| t.parse::<f32>())
-                        .collect::<Result<Vec<_>, _>>()
-                        .unwrap()
-                        .try_into()
-                        .unwrap(),
-                );
+                for name in tokens.by_ref() {
+                    let mtl_path = path.parent().unwrap_or_else(|| Path::new("")).join(name);
+                    obj.materials.extend(load_mtl(mtl_path)?);
+                }
             }
+            Some("v") => vs.push(tokens.parse_array()?),
             Some("vt") => {
-                vts.push(
-                    tokens
-                        .take(2)
-                        .map(|t| t.parse::<f32>())
-                        .collect::<Result<Vec<_>, _>>()
-                        .unwrap()
-                        .try_into()
-                        .unwrap(),
-                );
-            }
-            Some("vn") => {
-                vns.push(
-                    tokens
-                        .take(3)
-                        .map(|t| t.parse::<f32>())
-                        .collect::<Result<Vec<_>, _>>()
-                        .unwrap()
-                        .try_into()
-                        .unwrap(),
-                );
+                // `v` defaults to 0, and the optional third (w) component is ignored.
+                let u = tokens.parse()?;
+                let v = match tokens.next() {
+                    Some(token) => tokens.parse_token(token)?,
+                    None => 0.0,
+                };
+                vts.push([u, v]);
             }
+            Some("vn") => vns.push(tokens.parse_array()?),
             Some("f") => {
-                let vs = tokens
-                    .map(|t| {
-                        t.split("/")
-                            .map(|n| n.parse::<i32>().unwrap_or(0))
-                            .collect::<Vec<_>>()
-                            .try_into()
-                            .unwrap()
-                    })
-                    .collect::<Vec<_>>();
-                match vs.len() {
-                    3 => fs.push(([vs[0], vs[1], vs[2]], mtl)),
-                    4 => {
-                        fs.push(([vs[0], vs[1], vs[2]], mtl));
-                        fs.push(([vs[2], vs[3], vs[0]], mtl));
+                let mut vertexes = vec![];
+                while let Some(t) = tokens.next() {
+                    let mut indices = t.split('/');
+                    let mut index = |required: bool| -> Result<Option<i64>, Error> {
+                        match indices.next() {
+                            Some("") | None if !required => Ok(None),
+                            Some(n) => n.parse().map(Some).map_err(|_| {
+                                tokens.error_at(t, format!("invalid index `{}` in `{}`", n, t))
+                            }),
+                            None => Err(tokens.error_at(t, format!("invalid vertex `{}`", t))),
+                        }
+                    };
+                    let (v, vt, vn) = (index(true)?, index(false)?, index(false)?);
+                    if indices.next().is_some() {
+                        return Err(tokens.error_at(t, format!("invalid vertex `{}`", t)));
                     }
-                    _ => panic!("multi-poly"),
+                    vertexes.push((
+                        get(&vs, v.unwrap(), path, tokens.line)?,
+                        vt.map(|vt| get(&vts, vt, path, tokens.line)).transpose()?,
+                        vn.map(|vn| get(&vns, vn, path, tokens.line)).transpose()?,
+                    ));
+                }
+                if vertexes.len() < 3 {
+                    return Err(tokens.error_at(
+                        &line,
+                        format!("a face needs at least 3 vertices, found {}", vertexes.len()),
+                    ));
+                }
+                for i in 1..vertexes.len() - 1 {
+                    let triangle = [vertexes[0], vertexes[i], vertexes[i + 1]];
+                    let flat_normal = flat_normal(triangle.map(|v| v.0));
+                    obj.faces.push(Face {
+                        vertexes: triangle.map(|(v, vt, vn)| {
+                            (v, vt.unwrap_or([0.0, 0.0]), vn.unwrap_or(flat_normal))
+                        }),
+                        material,
+                        object,
+                        group,
+                        smoothing_group,
+                    });
                 }
             }
             Some("usemtl") => {
-                let mtl_name = tokens.next().unwrap();
-                mtl = ms.iter().position(|m| m.name == mtl_name).unwrap_or(0) as i32;
+                let mtl_name = tokens.expect()?;
+                material = Some(
+                    obj.materials
+                        .iter()
+                        .position(|m| m.name == mtl_name)
+                        .ok_or_else(|| Error::MissingMaterial {
+                            path: path.to_owned(),
+                            line: tokens.line,
+                            name: mtl_name.to_string(),
+                        })?,
+                );
+            }
+            Some("o") => {
+                obj.objects.push(tokens.rest());
+                object = Some(obj.objects.len() - 1);
+            }
+            Some("g") => {
+                let name = tokens.rest();
+                group = Some(
+                    obj.groups
+                        .iter()
+                        .position(|g| *g == name)
+                        .unwrap_or_else(|| {
+                            obj.groups.push(name.clone());
+                            obj.groups.len() - 1
+                        }),
+                );
+            }
+            Some("s") => {
+                smoothing_group = match tokens.expect()? {
+                    "off" => 0,
+                    token => tokens.parse_token(token)?,
+                };
             }
             Some(_) => {}
             None => {}
         }
     }
 
-    (
-        fs.iter()
-            .map(|f| {
-                (
-                    [
-                        (
-                            vs[f.0[0][0] as usize - 1],
-                            vts[f.0[0][1] as usize - 1],
-                            vns[f.0[0][2] as usize - 1],
-                        ),
-                        (
-                            vs[f.0[1][0] as usize - 1],
-                            vts[f.0[1][1] as usize - 1],
-                            vns[f.0[1][2] as usize - 1],
-                        ),
-                        (
-                            vs[f.0[2][0] as usize - 1],
-                            vts[f.0[2][1] as usize - 1],
-                            vns[f.0[2][2] as usize - 1],
-                        ),
-                    ],
-                    f.1,
-                )
-            })
-            .collect(),
-        ms,
-    )
+    Ok(obj)
+}
+
+fn flat_normal([v0, v1, v2]: [[f32; 3]; 3]) -> [f32; 3] {
+    let e1 = [v1[0] - v0[0], v1[1] - v0[1], v1[2] - v0[2]];
+    let e2 = [v2[0] - v0[0], v2[1] - v0[1], v2[2] - v0[2]];
+    let n = [
+        e1[1] * e2[2] - e1[2] * e2[1],
+        e1[2] * e2[0] - e1[0] * e2[2],
+        e1[0] * e2[1] - e1[1] * e2[0],
+    ];
+    let norm = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if norm == 0.0 {
+        n
+    } else {
+        n.map(|x| x / norm)
+    }
 }
 
 pub struct Material {
-    name: String,
-    ns: f64,
-    ka: [f64; 3], // ambient color
-    kd: [f64; 3], // diffuse color
-    ks: [f64; 3], // specular color
-    ke: [f64; 3],
-    map_kd: String,
-    ni: f64, // shineness
-    d: f64,  // opacity
-    illum: bool,
-}
-
-pub fn load_mtl(mtl_path: impl AsRef<Path>) -> Vec<Material> {
-    let file = File::open(mtl_path).unwrap();
-    let reader = BufReader::new(file);
-
-    let mut ms = vec![];
-
-    for line in reader.lines() {
-        let line = line.unwrap();
-        let mut tokens = line.split_whitespace();
-        match tokens.next() {
-            Some("newmtl") => ms.push(Material {
-                name: tokens.next().unwrap().to_string(),
+    pub name: String,
+    /// Specular exponent.
+    pub ns: f64,
+    /// Ambient color.
+    pub ka: [f64; 3],
+    /// Diffuse color.
+    pub kd: Option<[f64; 3]>,
+    /// Specular color.
+    pub ks: Option<[f64; 3]>,
+    /// Emissive color.
+    pub ke: [f64; 3],
+    /// Diffuse texture, relative to the working directory.
+    pub map_kd: Option<PathBuf>,
+    /// Index of refraction.
+    pub ni: Option<f64>,
+    /// Opacity.
+    pub d: f64,
+    /// Illumination model.
+    pub illum: i32,
+    /// Roughness, from the PBR extension like the following ones.
+    pub pr: Option<f64>,
+    /// Metallic.
+    pub pm: Option<f64>,
+    /// Sheen.
+    pub ps: Option<f64>,
+    /// Clearcoat thickness.
+    pub pc: Option<f64>,
+    /// Clearcoat roughness.
+    pub pcr: Option<f64>,
+}
+
+pub fn load_mtl(mtl_path: impl AsRef<Path>) -> Result<Vec<Material>, Error> {
+    let path = mtl_path.as_ref();
+    let reader = open(path)?;
+
+    let mut ms: Vec<Material> = vec![];
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        let mut tokens = Tokens::new(&line, path, i + 1);
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        if keyword == "newmtl" {
+            ms.push(Material {
+                name: tokens.expect()?.to_string(),
                 ns: 0.0,
                 ka: [0.0; 3],
-                kd: [0.0; 3],
-                ks: [0.0; 3],
+                kd: None,
+                ks: None,
                 ke: [0.0; 3],
-                map_kd: String::new(),
-                ni: 0.0,
-                d: 0.0,
-                illum: false,
-            }),
-            Some("Ns") => {
-                ms.last_mut().unwrap().ns = tokens.next().unwrap().parse().unwrap();
-            }
-            Some("Ka") => {
-                ms.last_mut().unwrap().ka = tokens
-                    .take(3)
-                    .map(|t| t.parse::<f64>())
-                    .collect::<Result<Vec<_>, _>>()
-                    .unwrap()
-                    .try_into()
-                    .unwrap();
-            }
-            Some("Kd") => {
-                ms.last_mut().unwrap().kd = tokens
-                    .take(3)
-                    .map(|t| t.parse::<f64>())
-                    .collect::<Result<Vec<_>, _>>()
-                    .unwrap()
-                    .try_into()
-                    .unwrap();
-            }
-            Some("Ks") => {
-                ms.last_mut().unwrap().ks = tokens
-                    .take(3)
-                    .map(|t| t.parse::<f64>())
-                    .collect::<Result<Vec<_>, _>>()
-                    .unwrap()
-                    .try_into()
-                    .unwrap();
-            }
-            Some("Ke") => {
-                ms.last_mut().unwrap().ke = tokens
-                    .take(3)
-                    .map(|t| t.parse::<f64>())
-                    .collect::<Result<Vec<_>, _>>()
-                    .unwrap()
-                    .try_into()
-                    .unwrap();
-            }
-            Some("map_Kd") => {
-                ms.last_mut().unwrap().map_kd = tokens.next().unwrap().to_string();
-            }
-            Some("Ni") => {
-                ms.last_mut().unwrap().ni = tokens.next().unwrap().parse().unwrap();
-            }
-            Some("d") => {
-                ms.last_mut().unwrap().d = tokens.next().unwrap().parse().unwrap();
-            }
-            Some("illum") => {
-                ms.last_mut().unwrap().illum = tokens.next().unwrap().parse::<i32>().unwrap() == 1;
+                map_kd: None,
+                ni: None,
+                d: 1.0,
+                illum: 1,
+                pr: None,
+                pm: None,
+                ps: None,
+                pc: None,
+                pcr: None,
+            });
+            continue;
+        }
+        if keyword.starts_with('#') {
+            continue;
+        }
+        let Some(m) = ms.last_mut() else {
+            return Err(tokens.error_at(keyword, format!("`{}` before `newmtl`", keyword)));
+        };
+        match keyword {
+            "Ns" => m.ns = tokens.parse()?,
+            "Ka" => m.ka = tokens.parse_array()?,
+            "Kd" => m.kd = Some(tokens.parse_array()?),
+            "Ks" => m.ks = Some(tokens.parse_array()?),
+            "Ke" => m.ke = tokens.parse_array()?,
+            "map_Kd" => {
+                // Options such as `-s 1 1 1` precede the file name.
+                let Some(name) = tokens.by_ref().last() else {
+                    return Err(tokens.end_of_line());
+                };
+                m.map_kd = Some(path.parent().unwrap_or_else(|| Path::new("")).join(name));
             }
+            "Ni" => m.ni = Some(tokens.parse()?),
+            "d" => m.d = tokens.parse()?,
+            "Tr" => m.d = 1.0 - tokens.parse::<f64>()?,
+            "illum" => m.illum = tokens.parse()?,
+            "Pr" => m.pr = Some(tokens.parse()?),
+            "Pm" => m.pm = Some(tokens.parse()?),
+            "Ps" => m.ps = Some(tokens.parse()?),
+            "Pc" => m.pc = Some(tokens.parse()?),
+            "Pcr" => m.pcr = Some(tokens.parse()?),
             _ => {}
         }
     }
-    ms
+    Ok(ms)
+}
+
+impl<'a> From<&Material> for materials::Basic<'a> {
+    /// Picks the silver material closest to the MTL description, a principled one when it
+    /// uses the PBR extension, whose specular level is the mean of `Ks`. `map_Kd` is not
+    /// applied here. Missing colors are black, and dielectrics without `Ni` are glass.
+    fn from(m: &Material) -> Self {
+        let is_black = |c: [f64; 3]| c.iter().all(|&x| x <= 0.0);
+        let (kd, ks) = (m.kd.unwrap_or_default(), m.ks.unwrap_or_default());
+        if m.pr.is_some() || m.pm.is_some() {
+            let clearcoat_roughness = m.pcr.unwrap_or(0.1);
+            let principled = materials::principled::Principled::new(Vec3::new(kd))
+                .with_metallic(m.pm.unwrap_or(0.0))
+                .with_roughness(m.pr.unwrap_or(0.5))
+                .with_transmission(1.0 - m.d, m.ni.unwrap_or(1.5))
+                .with_clearcoat(m.pc.unwrap_or(0.0), clearcoat_roughness)
+                .with_sheen(m.ps.unwrap_or(0.0))
+                .with_emission(Vec3::new(m.ke));
+            materials::Basic::Principled(match m.ks {
+                Some(ks) => principled.with_specular(ks.iter().sum::<f64>() / 3.0),
+                None => principled,
+            })
+        } else if !is_black(m.ke) {
+            materials::Basic::DiffuseLight(materials::DiffuseLight::new(Vec3::new(m.ke)))
+        } else if m.d < 1.0 || matches!(m.illum, 4 | 6 | 7 | 9) {
+            materials::Basic::Dielectric(materials::Dielectric::new(m.ni.unwrap_or(1.5)))
+        } else if m.illum == 3 || (is_black(kd) && !is_black(ks)) {
+            // Phong exponent to roughness
+            let fuzz = (2.0 / (m.ns + 2.0)).sqrt();
+            materials::Basic::Metal(materials::Metal::new(Vec3::new(ks), fuzz))
+        } else {
+            materials::Basic::Lambertian(materials::Lambertian::new(Vec3::new(kd)))
+        }
+    }
+}
+
+type Texture = Arc<dyn Fn([f32; 2]) -> Vec3 + Send + Sync>;
+
+/// Loads an OBJ file as shapes paired with the materials of its MTL files.
+/// Faces without a material are light gray Lambertian. `map_Kd` is the base color of
+/// diffuse and principled materials, and is ignored by the others.
+pub fn load_scene(
+    obj_path: &str,
+) -> Result<Vec<(shapes::Basic, materials::Basic<'static>)>, Error> {
+    let obj = load(obj_path)?;
+    let materials: Vec<materials::Basic> = obj.materials.iter().map(|m| m.into()).collect();
+    let textures = obj
+        .materials
+        .iter()
+        .map(texture)
+        .collect::<Result<Vec<_>, _>>()?;
+    let default = materials::Basic::Lambertian(materials::Lambertian::new(Vec3::new([0.8; 3])));
+
+    let to_vec3 = |a: [f32; 3]| Vec3::new(a.map(|x| x as f64));
+    Ok(obj
+        .faces
+        .iter()
+        .map(|f| {
+            let [v0, v1, v2] = f.vertexes.map(|(v, _, _)| to_vec3(v));
+            let normals = f.vertexes.map(|(_, _, n)| n);
+            let shape = if normals[0] == normals[1] && normals[1] == normals[2] {
+                shapes::Basic::TriangleBothSide(shapes::Triangle::new(v0, v1, v2))
+            } else {
+                shapes::Basic::TriangleWithNormalsBothSide(
+                    shapes::triangle_with_normals::TriangleWithNormals::new(
+                        [v0, v1, v2],
+                        normals.map(to_vec3),
+                    ),
+                )
+            };
+            let tex = |pixel: &Texture| {
+                materials::tex::Tex::new(pixel.clone(), f.vertexes.map(|(_, vt, _)| vt))
+            };
+            // The texture is the base color of diffuse and principled materials only.
+            let material = match f.material {
+                Some(i) => match (&textures[i], &materials[i]) {
+                    (Some(pixel), materials::Basic::Lambertian(_)) => {
+                        materials::Basic::Tex(tex(pixel))
+                    }
+                    (Some(pixel), materials::Basic::Principled(principled)) => {
+                        materials::Basic::Tex(tex(pixel).with_principled(principled.clone()))
+                    }
+                    (_, material) => material.clone(),
+                },
+                None => default.clone(),
+            };
+            (shape, material)
+        })
+        .collect())
+}
+
+#[cfg(feature = "image")]
+fn texture(m: &Material) -> Result<Option<Texture>, Error> {
+    let Some(path) = &m.map_kd else {
+        return Ok(None);
+    };
+    let image = materials::tex::Image::open(path)?;
+    // Without `Kd`, the texture is the color.
+    let kd = Vec3::new(m.kd.unwrap_or([1.0; 3]));
+    Ok(Some(Arc::new(move |[u, v]: [f32; 2]| {
+        let (w, h) = (image.width() as f32, image.height() as f32);
+        crate::util::gamma_to_linear(image.get([u * w, v * h]), 2.2) * kd
+    })))
+}
+
+#[cfg(not(feature = "image"))]
+fn texture(_m: &Material) -> Result<Option<Texture>, Error> {
+    Ok(None)
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, Error> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })
+}
+
+/// Looks up an OBJ index, which is 1-based or, if negative, relative to the end of `items`.
+fn get<T: Copy>(items: &[T], index: i64, path: &Path, line: usize) -> Result<T, Error> {
+    let i = if index < 0 {
+        items.len() as i64 + index
+    } else {
+        index - 1
+    };
+    usize::try_from(i)
+        .ok()
+        .and_then(|i| items.get(i).copied())
+        .ok_or_else(|| Error::BadIndex {
+            path: path.to_owned(),
+            line,
+            index,
+        })
+}
+
+/// Whitespace separated tokens of a line, remembering where they came from.
+struct Tokens<'a> {
+    source: &'a str,
+    tokens: std::str::SplitWhitespace<'a>,
+    path: &'a Path,
+    line: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(source: &'a str, path: &'a Path, line: usize) -> Self {
+        Tokens {
+            source,
+            tokens: source.split_whitespace(),
+            path,
+            line,
+        }
+    }
+
+    /// `token` must be a subslice of the line.
+    fn error_at(&self, token: &str, message: String) -> Error {
+        Error::Parse {
+            path: self.path.to_owned(),
+            line: self.line,
+            column: token.as_ptr() as usize - self.source.as_ptr() as usize + 1,
+            message,
+        }
+    }
+
+    fn end_of_line(&self) -> Error {
+        Error::Parse {
+            path: self.path.to_owned(),
+            line: self.line,
+            column: self.source.len() + 1,
+            message: "unexpected end of line".to_string(),
+        }
+    }
+
+    fn expect(&mut self) -> Result<&'a str, Error> {
+        self.tokens.next().ok_or_else(|| self.end_of_line())
+    }
+
+    fn parse<T: FromStr>(&mut self) -> Result<T, Error>
+    where
+        T::Err: Display,
+    {
+        let token = self.expect()?;
+        self.parse_token(token)
+    }
+
+    fn parse_token<T: FromStr>(&self, token: &str) -> Result<T, Error>
+    where
+        T::Err: Display,
+    {
+        token
+            .parse()
+            .map_err(|e| self.error_at(token, format!("invalid number `{}`: {}", token, e)))
+    }
+
+    /// The remaining tokens joined by a space.
+    fn rest(&mut self) -> String {
+        self.tokens.by_ref().collect::<Vec<_>>().join(" ")
+    }
+
+    fn parse_array<T: FromStr + Copy + Default, const N: usize>(&mut self) -> Result<[T; N], Error>
+    where
+        T::Err: Display,
+    {
+        let mut array = [T::default(); N];
+        for x in &mut array {
+            *x = self.parse()?;
+        }
+        Ok(array)
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.tokens.next()
+    }
+}
+
+#[test]
+fn test() {
+    let obj = load("./cube.obj").unwrap();
+    assert_eq!(obj.faces.len(), 12);
+    assert_eq!(obj.materials.len(), 1);
+    assert_eq!(obj.objects, vec!["Cube".to_string()]);
+
+    assert!(matches!(load("./missing.obj"), Err(Error::Io { .. })));
+
+    let scene = load_scene("./cube.obj").unwrap();
+    assert_eq!(scene.len(), 12);
+    assert!(matches!(scene[0].1, materials::Basic::Lambertian(_)));
+
+    // Unique to this process, for concurrent test runs.
+    let temp =
+        |name: &str| std::env::temp_dir().join(format!("silver_{}_{name}", std::process::id()));
+    let path = temp("pentagon.obj");
+    std::fs::write(
+        &path,
+        "o Pentagon\n\
+         v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0.5 1.5 0\nv 0 1 0\nvn 0 0 1\n\
+         g a b\ns 1\nf -5//1 -4//1 -3//1 -2 -1\ns off\nf 1 2 3\n",
+    )
+    .unwrap();
+    let obj = load(path.to_str().unwrap()).unwrap();
+    assert_eq!(obj.faces.len(), 4);
+    assert_eq!(obj.groups, vec!["a b".to_string()]);
+    assert_eq!(obj.faces[3].vertexes[0].1, [0.0, 0.0]);
+    assert_eq!(obj.faces[1].vertexes[2].2, [0.0, 0.0, 1.0]);
+    assert_eq!(obj.faces[1].smoothing_group, 1);
+    assert_eq!(obj.faces[3].smoothing_group, 0);
+
+    let mtl_path = temp("gold.mtl");
+    std::fs::write(&mtl_path, "newmtl gold\nKd 1 0.8 0.3\nPm 1\nPr 0.2\n").unwrap();
+    let mtl = load_mtl(&mtl_path).unwrap();
+    assert_eq!(
+        (mtl[0].pm, mtl[0].pr, mtl[0].ps),
+        (Some(1.0), Some(0.2), None)
+    );
+    assert!(matches!(
+        materials::Basic::from(&mtl[0]),
+        materials::Basic::Principled(_)
+    ));
+    // PBR glass without `Ni` still refracts, with the specular level of `Ks`.
+    std::fs::write(
+        &mtl_path,
+        "newmtl glass\nKd 1 1 1\nKs 0.25 0.25 0.25\nPm 0\nd 0\n",
+    )
+    .unwrap();
+    let mtl = load_mtl(&mtl_path).unwrap();
+    let materials::Basic::Principled(glass) = materials::Basic::from(&mtl[0]) else {
+        panic!("not a principled material");
+    };
+    assert_eq!(
+        glass,
+        materials::principled::Principled::new(Vec3::new([1.0; 3]))
+            .with_transmission(1.0, 1.5)
+            .with_specular(0.25)
+    );
+
+    // Without `Kd`, a white texture stays white. Only diffuse and principled materials
+    // take it as their base color.
+    let mtl_path = temp("textured.mtl");
+    let white = temp("white.png");
+    let white = white.file_name().unwrap().to_str().unwrap();
+    std::fs::write(
+        &mtl_path,
+        format!(
+            "newmtl textured\nmap_Kd {white}\n\
+             newmtl glass\nmap_Kd {white}\nd 0.5\n\
+             newmtl lamp\nmap_Kd {white}\nKe 4 4 4\n\
+             newmtl plastic\nmap_Kd {white}\nPr 0.5\n"
+        ),
+    )
+    .unwrap();
+    let mtl = load_mtl(&mtl_path).unwrap();
+    assert_eq!((mtl[0].kd, mtl[0].ni), (None, None));
+    #[cfg(feature = "image")]
+    {
+        image::RgbImage::from_pixel(2, 2, image::Rgb([255, 255, 255]))
+            .save(mtl[0].map_kd.as_ref().unwrap())
+            .unwrap();
+        let pixel = texture(&mtl[0]).unwrap().unwrap();
+        assert_eq!(pixel([0.5, 0.5]), Vec3::new([1.0; 3]));
+
+        let path = temp("textured.obj");
+        let mtl_name = mtl_path.file_name().unwrap().to_str().unwrap();
+        std::fs::write(
+            &path,
+            format!(
+                "mtllib {mtl_name}\nv 0 0 0\nv 1 0 0\nv 1 1 0\n\
+                 usemtl textured\nf 1 2 3\nusemtl glass\nf 1 2 3\n\
+                 usemtl lamp\nf 1 2 3\nusemtl plastic\nf 1 2 3\n"
+            ),
+        )
+        .unwrap();
+        let scene = load_scene(path.to_str().unwrap()).unwrap();
+        let materials: Vec<_> = scene.iter().map(|(_, m)| m).collect();
+        assert!(matches!(
+            materials.as_slice(),
+            [
+                materials::Basic::Tex(_),
+                materials::Basic::Dielectric(_),
+                materials::Basic::DiffuseLight(_),
+                materials::Basic::Tex(_),
+            ]
+        ));
+    }
+
+    let path = temp("vt.obj");
+    std::fs::write(
+        &path,
+        "v 0 0 0\nv 1 0 0\nv 1 1 0\nvt 0.25\nvt 0.5 0.75 0\nf 1/1 2/2 3/2\n",
+    )
+    .unwrap();
+    let obj = load(path.to_str().unwrap()).unwrap();
+    assert_eq!(obj.faces[0].vertexes[0].1, [0.25, 0.0]);
+    assert_eq!(obj.faces[0].vertexes[1].1, [0.5, 0.75]);
+
+    let path = temp("bad_index.obj");
+    std::fs::write(&path, "v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1/1 2/1 3/1\n").unwrap();
+    assert!(matches!(
+        load(path.to_str().unwrap()),
+        Err(Error::BadIndex {
+            line: 4,
+            index: 1,
+            ..
+        })
+    ));
 }
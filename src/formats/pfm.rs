@@ -0,0 +1,43 @@
+//! Portable FloatMap writer. Values are stored without gamma correction or clamping.
+
+use std::{io, path::Path};
+
+use crate::{framebuffer::FrameBuffer, Error};
+
+pub fn write(path: impl AsRef<Path>, image: &FrameBuffer) -> Result<(), Error> {
+    super::write_file(path.as_ref(), |w| write_to(w, image))
+}
+
+pub fn write_to(w: &mut impl io::Write, image: &FrameBuffer) -> io::Result<()> {
+    // A negative scale means little-endian.
+    write!(w, "PF\n{} {}\n-1.0\n", image.width(), image.height())?;
+    // Rows go from the bottom to the top.
+    for y in (0..image.height()).rev() {
+        for x in 0..image.width() {
+            let c = image.get(x, y);
+            for v in [c.r(), c.g(), c.b()] {
+                w.write_all(&(v as f32).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test() {
+    use crate::vec3::Vec3;
+
+    let image = FrameBuffer::from_fn(3, 2, |x, y| Vec3::new([x as f64, y as f64, -0.5]));
+    let mut data = vec![];
+    write_to(&mut data, &image).unwrap();
+    let header = b"PF\n3 2\n-1.0\n";
+    assert_eq!(&data[..header.len()], header);
+    let floats: Vec<f32> = data[header.len()..]
+        .chunks(4)
+        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+        .collect();
+    assert_eq!(floats.len(), 3 * 2 * 3);
+    // The bottom row comes first.
+    assert_eq!(floats[..3], [0.0, 1.0, -0.5]);
+    assert_eq!(floats[15..], [2.0, 0.0, -0.5]);
+}
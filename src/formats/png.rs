@@ -0,0 +1,42 @@
+//! PNG writer using the `image` crate
+
+use std::path::Path;
+
+use crate::{framebuffer::FrameBuffer, Error};
+
+pub fn write(path: impl AsRef<Path>, image: &FrameBuffer) -> Result<(), Error> {
+    let path = path.as_ref();
+    let data = image.to_rgb8().concat();
+    image::save_buffer(
+        path,
+        &data,
+        image.width() as u32,
+        image.height() as u32,
+        image::ColorType::Rgb8,
+    )
+    .map_err(|e| match e {
+        image::ImageError::IoError(source) => Error::Io {
+            path: path.to_owned(),
+            source,
+        },
+        e => Error::Image {
+            path: path.to_owned(),
+            message: e.to_string(),
+        },
+    })
+}
+
+#[test]
+fn test() {
+    use crate::vec3::Vec3;
+
+    let image = FrameBuffer::from_fn(3, 2, |x, y| Vec3::new([x as f64 / 2.0, y as f64, 2.0]));
+    let path = std::env::temp_dir().join(format!("silver_{}_png_test.png", std::process::id()));
+    write(&path, &image).unwrap();
+
+    let decoded = image::open(&path).unwrap().into_rgb8();
+    assert_eq!((decoded.width(), decoded.height()), (3, 2));
+    assert_eq!(decoded.as_raw(), &image.to_rgb8().concat());
+    assert_eq!(decoded.get_pixel(2, 1).0, [255; 3]);
+    assert_eq!(decoded.get_pixel(0, 0).0, [0, 0, 255]);
+}
@@ -0,0 +1,17 @@
+//! Binary PPM (P6) writer
+
+use std::{io, path::Path};
+
+use crate::{framebuffer::FrameBuffer, Error};
+
+pub fn write(path: impl AsRef<Path>, image: &FrameBuffer) -> Result<(), Error> {
+    super::write_file(path.as_ref(), |w| write_to(w, image))
+}
+
+pub fn write_to(w: &mut impl io::Write, image: &FrameBuffer) -> io::Result<()> {
+    write!(w, "P6\n{} {}\n255\n", image.width(), image.height())?;
+    for rgb in image.to_rgb8() {
+        w.write_all(&rgb)?;
+    }
+    Ok(())
+}
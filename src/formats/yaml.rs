@@ -1,17 +1,61 @@
-use std::{fs, io};
+use std::{fs, io, path::Path};
 
-use crate::{materials::Basic as Material, shapes::Basic as Shape, vec3::Vec3};
+use crate::{
+    camera::Camera,
+    filter::Filter,
+    materials::{
+        atmosphere::Atmosphere,
+        dielectric::Dispersion,
+        heterogeneous_medium::{Density, Grid, Noise},
+        Basic as Material,
+    },
+    phase::{self, DoubleHenyeyGreenstein, HenyeyGreenstein, Isotropic, Rayleigh},
+    ray::Ray,
+    render::Adaptive,
+    shapes::Basic as Shape,
+    vec3::Vec3,
+    Error,
+};
 
-pub fn load(file: &str) -> Result<Vec<(Shape, Material)>, String> {
-    let file = fs::File::open(file).unwrap();
-    let reader = io::BufReader::new(file);
-    let scene: map::Scene = serde_yaml::from_reader(reader).map_err(|e| e.to_string())?;
+/// A whole render described by a YAML file.
+pub struct Scene {
+    pub camera: Camera,
+    pub width: i32,
+    pub height: i32,
+    pub sample_per_pixel: i32,
+    pub cutoff: i32,
+    pub filter: Filter,
+    pub adaptive: Option<Adaptive>,
+    /// Whether paths carry a wavelength, for dispersion.
+    pub spectral: bool,
+    /// Fog filling the scene.
+    pub atmosphere: Option<Atmosphere>,
+    pub env: fn(&Ray) -> Vec3,
+    pub objects: Vec<(Shape, Material<'static>)>,
+}
+
+pub fn load(file: &str) -> Result<Scene, Error> {
+    let path = Path::new(file);
+    let reader = fs::File::open(path)
+        .map(io::BufReader::new)
+        .map_err(|source| Error::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+    let scene: map::Scene = serde_yaml::from_reader(reader).map_err(|e| match e.location() {
+        Some(location) => Error::Parse {
+            path: path.to_owned(),
+            line: location.line(),
+            column: location.column(),
+            message: e.to_string(),
+        },
+        None => Error::Invalid {
+            path: path.to_owned(),
+            message: e.to_string(),
+        },
+    })?;
 
-    Ok(scene
-        .objects
-        .into_iter()
-        .map(|object| object.into())
-        .collect())
+    Ok(scene.into())
 }
 
 mod map {
@@ -19,9 +63,154 @@ mod map {
 
     #[derive(PartialEq, Debug, Serialize, Deserialize)]
     pub struct Scene {
+        #[serde(default)]
+        pub camera: Camera,
+        #[serde(default)]
+        pub render: Render,
+        #[serde(default)]
+        pub env: Env,
+        #[serde(default)]
+        pub atmosphere: Option<Atmosphere>,
         pub objects: Vec<Object>,
     }
 
+    /// Fog of `density` at `base_height`, falling off exponentially with the height.
+    #[derive(PartialEq, Debug, Serialize, Deserialize)]
+    pub struct Atmosphere {
+        pub density: f64,
+        pub color: [f64; 3],
+        #[serde(default)]
+        pub base_height: f64,
+        /// Uniform if zero.
+        #[serde(default)]
+        pub falloff: f64,
+        #[serde(default)]
+        pub phase: Option<Phase>,
+    }
+
+    #[derive(PartialEq, Debug, Serialize, Deserialize)]
+    pub struct Camera {
+        pub origin: [f64; 3],
+        pub target: [f64; 3],
+        #[serde(default = "default_vup")]
+        pub vup: [f64; 3],
+        /// Vertical field of view in degrees.
+        pub vfov: f64,
+        #[serde(default)]
+        pub diaphragm: f64,
+        /// Focus distance. Defaults to the distance between origin and target.
+        #[serde(default)]
+        pub dof: Option<f64>,
+    }
+
+    fn default_vup() -> [f64; 3] {
+        [0.0, 1.0, 0.0]
+    }
+
+    impl Default for Camera {
+        fn default() -> Self {
+            Camera {
+                origin: [0.0, 1.0, 2.0],
+                target: [0.0, 0.8, 0.0],
+                vup: default_vup(),
+                vfov: 60.0,
+                diaphragm: 0.0,
+                dof: None,
+            }
+        }
+    }
+
+    /// Missing settings take their default value.
+    #[derive(PartialEq, Debug, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct Render {
+        pub width: i32,
+        pub height: i32,
+        pub sample_per_pixel: i32,
+        pub cutoff: i32,
+        pub filter: Filter,
+        pub adaptive: Option<Adaptive>,
+        pub spectral: bool,
+    }
+
+    impl Default for Render {
+        fn default() -> Self {
+            Render {
+                width: 640,
+                height: 480,
+                sample_per_pixel: 20,
+                cutoff: 50,
+                filter: Filter::default(),
+                adaptive: None,
+                spectral: false,
+            }
+        }
+    }
+
+    /// Adaptive sampling. `sample_per_pixel` is then the average number of samples.
+    #[derive(PartialEq, Debug, Serialize, Deserialize)]
+    pub struct Adaptive {
+        /// Relative standard error at which pixels stop being sampled.
+        pub threshold: f64,
+        #[serde(default = "default_min_samples")]
+        pub min_samples: i32,
+        #[serde(default = "default_max_samples")]
+        pub max_samples: i32,
+    }
+
+    fn default_min_samples() -> i32 {
+        16
+    }
+
+    fn default_max_samples() -> i32 {
+        4096
+    }
+
+    /// Pixel reconstruction filter. Radiuses are in pixels.
+    #[derive(PartialEq, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Filter {
+        Box {
+            radius: f64,
+        },
+        Tent {
+            radius: f64,
+        },
+        Gaussian {
+            radius: f64,
+            alpha: f64,
+        },
+        Mitchell {
+            radius: f64,
+            #[serde(default = "one_third")]
+            b: f64,
+            #[serde(default = "one_third")]
+            c: f64,
+        },
+        Lanczos {
+            radius: f64,
+        },
+    }
+
+    impl Default for Filter {
+        fn default() -> Self {
+            Filter::Box { radius: 0.5 }
+        }
+    }
+
+    fn one_third() -> f64 {
+        1.0 / 3.0
+    }
+
+    #[derive(PartialEq, Debug, Default, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Env {
+        #[default]
+        Default,
+        Fancy,
+        Dark,
+    }
+
     #[derive(PartialEq, Debug, Serialize, Deserialize)]
     pub struct Object {
         pub shape: Shape,
@@ -31,38 +220,429 @@ mod map {
     #[derive(PartialEq, Debug, Serialize, Deserialize)]
     #[serde(rename_all = "snake_case")]
     pub enum Shape {
-        Sphere { center: [f64; 3], radius: f64 },
+        Sphere {
+            center: [f64; 3],
+            radius: f64,
+        },
+        Triangle {
+            vertexes: [[f64; 3]; 3],
+        },
+        TriangleBothSide {
+            vertexes: [[f64; 3]; 3],
+        },
+        TriangleWithNormalsBothSide {
+            vertexes: [[f64; 3]; 3],
+            normals: [[f64; 3]; 3],
+        },
+        Edge {
+            vertexes: [[f64; 3]; 2],
+            radiuses: [f64; 2],
+        },
     }
 
     #[derive(PartialEq, Debug, Serialize, Deserialize)]
     #[serde(rename_all = "snake_case")]
     pub enum Material {
-        Lambertian { color: [f64; 3] },
+        Lambertian {
+            color: [f64; 3],
+        },
+        Dielectric {
+            ri: f64,
+            /// Only seen in spectral renders.
+            #[serde(default)]
+            dispersion: Option<Dispersion>,
+            #[serde(default)]
+            absorption: Option<Absorption>,
+            #[serde(default)]
+            thin_walled: bool,
+        },
+        Metal {
+            color: [f64; 3],
+            #[serde(default)]
+            fuzz: f64,
+        },
+        DiffuseLight {
+            color: [f64; 3],
+        },
+        Checker {
+            odd: Box<Material>,
+            even: Box<Material>,
+        },
+        ConstantMedium {
+            density: f64,
+            color: [f64; 3],
+            /// Isotropic if missing.
+            #[serde(default)]
+            phase: Option<Phase>,
+        },
+        HeterogeneousMedium {
+            density: Density,
+            color: [f64; 3],
+            #[serde(default)]
+            phase: Option<Phase>,
+        },
+        WetGlass {
+            center: [f64; 3],
+        },
+        /// A metal of complex index of refraction `eta + i k`.
+        Conductor {
+            eta: [f64; 3],
+            k: [f64; 3],
+            #[serde(default)]
+            roughness: f64,
+        },
+        RoughDielectric {
+            ri: f64,
+            #[serde(default)]
+            roughness: f64,
+        },
+        Principled {
+            color: [f64; 3],
+            #[serde(default)]
+            metallic: f64,
+            #[serde(default = "one_half")]
+            roughness: f64,
+            #[serde(default = "one_half")]
+            specular: f64,
+            #[serde(default)]
+            transmission: f64,
+            #[serde(default = "default_ior")]
+            ior: f64,
+            #[serde(default)]
+            clearcoat: f64,
+            #[serde(default = "default_clearcoat_roughness")]
+            clearcoat_roughness: f64,
+            #[serde(default)]
+            sheen: f64,
+            #[serde(default)]
+            emission: [f64; 3],
+        },
+    }
+
+    /// Density of a heterogeneous medium, in world space.
+    #[derive(PartialEq, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Density {
+        /// Values ordered by x, then y, then z, zero out of the box.
+        Grid {
+            min: [f64; 3],
+            max: [f64; 3],
+            size: [usize; 3],
+            values: Vec<f64>,
+        },
+        /// Fractal noise from 0 to `density`, with features up to `scale` wide.
+        Noise {
+            density: f64,
+            scale: f64,
+            #[serde(default = "default_octaves")]
+            octaves: u32,
+            #[serde(default)]
+            threshold: f64,
+            #[serde(default)]
+            seed: u64,
+        },
+    }
+
+    fn default_octaves() -> u32 {
+        4
+    }
+
+    /// Phase function of a medium.
+    #[derive(PartialEq, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Phase {
+        Isotropic,
+        HenyeyGreenstein {
+            g: f64,
+        },
+        Rayleigh,
+        /// `weight` is that of the forward lobe.
+        DoubleHenyeyGreenstein {
+            forward: f64,
+            backward: f64,
+            weight: f64,
+        },
+        Mie,
+    }
+
+    /// Color of the light after travelling `distance` in a dielectric.
+    #[derive(PartialEq, Debug, Serialize, Deserialize)]
+    pub struct Absorption {
+        pub color: [f64; 3],
+        pub distance: f64,
+    }
+
+    #[derive(PartialEq, Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Dispersion {
+        Cauchy { a: f64, b: f64 },
+        Sellmeier { b: [f64; 3], c: [f64; 3] },
+        Bk7,
+        Diamond,
+    }
+
+    fn one_half() -> f64 {
+        0.5
+    }
+
+    fn default_ior() -> f64 {
+        1.5
+    }
+
+    fn default_clearcoat_roughness() -> f64 {
+        0.1
     }
 }
 
-impl<'a> Into<(Shape, Material<'a>)> for map::Object {
-    fn into(self) -> (Shape, Material<'a>) {
-        (self.shape.into(), self.material.into())
+impl From<map::Scene> for Scene {
+    fn from(scene: map::Scene) -> Self {
+        let map::Render {
+            width,
+            height,
+            sample_per_pixel,
+            cutoff,
+            filter,
+            adaptive,
+            spectral,
+        } = scene.render;
+        let map::Camera {
+            origin,
+            target,
+            vup,
+            vfov,
+            diaphragm,
+            dof,
+        } = scene.camera;
+        let (origin, target) = (Vec3::new(origin), Vec3::new(target));
+        let camera = Camera::new(
+            &origin,
+            &target,
+            &Vec3::new(vup),
+            vfov.to_radians(),
+            width as f64 / height as f64,
+            diaphragm,
+            dof.unwrap_or_else(|| (target - origin).norm()),
+        );
+
+        Scene {
+            camera,
+            width,
+            height,
+            sample_per_pixel,
+            cutoff,
+            filter: filter.into(),
+            adaptive: adaptive
+                .map(|a| Adaptive::new(a.threshold).with_samples(a.min_samples, a.max_samples)),
+            spectral,
+            atmosphere: scene.atmosphere.map(|a| {
+                Atmosphere::new(a.density, Vec3::new(a.color))
+                    .with_height_falloff(a.base_height, a.falloff)
+                    .with_phase(a.phase.map_or_else(Default::default, Into::into))
+            }),
+            env: scene.env.into(),
+            objects: scene.objects.into_iter().map(|o| o.into()).collect(),
+        }
+    }
+}
+
+impl From<map::Env> for fn(&Ray) -> Vec3 {
+    fn from(env: map::Env) -> Self {
+        match env {
+            map::Env::Default => crate::envs::default_env,
+            map::Env::Fancy => crate::envs::fancy_env,
+            map::Env::Dark => crate::envs::dark_env,
+        }
+    }
+}
+
+impl From<map::Filter> for Filter {
+    fn from(filter: map::Filter) -> Self {
+        match filter {
+            map::Filter::Box { radius } => Filter::Box { radius },
+            map::Filter::Tent { radius } => Filter::Tent { radius },
+            map::Filter::Gaussian { radius, alpha } => Filter::Gaussian { radius, alpha },
+            map::Filter::Mitchell { radius, b, c } => Filter::Mitchell { radius, b, c },
+            map::Filter::Lanczos { radius } => Filter::Lanczos { radius },
+        }
     }
 }
 
-impl Into<Shape> for map::Shape {
-    fn into(self) -> Shape {
-        match self {
+impl<'a> From<map::Object> for (Shape, Material<'a>) {
+    fn from(object: map::Object) -> Self {
+        (object.shape.into(), object.material.into())
+    }
+}
+
+impl From<map::Shape> for Shape {
+    fn from(shape: map::Shape) -> Self {
+        use crate::shapes::{
+            edge::Edge, triangle_with_normals::TriangleWithNormals, Sphere, Triangle,
+        };
+
+        match shape {
             map::Shape::Sphere { center, radius } => {
-                Shape::Sphere(crate::shapes::Sphere::new(Vec3::new(center), radius))
+                Shape::Sphere(Sphere::new(Vec3::new(center), radius))
             }
+            map::Shape::Triangle {
+                vertexes: [a, b, c],
+            } => Shape::Triangle(Triangle::new(Vec3::new(a), Vec3::new(b), Vec3::new(c))),
+            map::Shape::TriangleBothSide {
+                vertexes: [a, b, c],
+            } => Shape::TriangleBothSide(Triangle::new(Vec3::new(a), Vec3::new(b), Vec3::new(c))),
+            map::Shape::TriangleWithNormalsBothSide { vertexes, normals } => {
+                Shape::TriangleWithNormalsBothSide(TriangleWithNormals::new(
+                    vertexes.map(Vec3::new),
+                    normals.map(Vec3::new),
+                ))
+            }
+            map::Shape::Edge {
+                vertexes: [a, b],
+                radiuses,
+            } => Shape::Edge(Edge::new([Vec3::new(a), Vec3::new(b)], radiuses)),
         }
     }
 }
 
-impl<'a> Into<Material<'a>> for map::Material {
-    fn into(self) -> Material<'a> {
-        match self {
+impl<'a> From<map::Material> for Material<'a> {
+    fn from(material: map::Material) -> Self {
+        use crate::materials::{
+            checker::Checker, conductor::Conductor, constant_medium::ConstantMedium,
+            heterogeneous_medium::HeterogeneousMedium, principled::Principled,
+            rough_dielectric::RoughDielectric, wet_glass::WetGlass, Dielectric, DiffuseLight,
+            Lambertian, Metal,
+        };
+
+        match material {
             map::Material::Lambertian { color } => {
-                Material::Lambertian(crate::materials::Lambertian::new(Vec3::new(color)))
+                Material::Lambertian(Lambertian::new(Vec3::new(color)))
+            }
+            map::Material::Dielectric {
+                ri,
+                dispersion,
+                absorption,
+                thin_walled,
+            } => {
+                let mut dielectric = Dielectric::new(ri).with_thin_walled(thin_walled);
+                if let Some(dispersion) = dispersion {
+                    dielectric = dielectric.with_dispersion(dispersion.into());
+                }
+                if let Some(map::Absorption { color, distance }) = absorption {
+                    dielectric = dielectric.with_absorption(Vec3::new(color), distance);
+                }
+                Material::Dielectric(dielectric)
+            }
+            map::Material::Metal { color, fuzz } => {
+                Material::Metal(Metal::new(Vec3::new(color), fuzz))
+            }
+            map::Material::DiffuseLight { color } => {
+                Material::DiffuseLight(DiffuseLight::new(Vec3::new(color)))
+            }
+            map::Material::Checker { odd, even } => Material::Checker(Checker::new(
+                Box::new((*odd).into()),
+                Box::new((*even).into()),
+            )),
+            map::Material::ConstantMedium {
+                density,
+                color,
+                phase,
+            } => Material::ConstantMedium(
+                ConstantMedium::new(density, Vec3::new(color))
+                    .with_phase(phase.map_or_else(Default::default, Into::into)),
+            ),
+            map::Material::HeterogeneousMedium {
+                density,
+                color,
+                phase,
+            } => Material::HeterogeneousMedium(
+                HeterogeneousMedium::new(density.into(), Vec3::new(color))
+                    .with_phase(phase.map_or_else(Default::default, Into::into)),
+            ),
+            map::Material::WetGlass { center } => {
+                Material::WetGlass(WetGlass::new(Vec3::new(center)))
+            }
+            map::Material::Conductor { eta, k, roughness } => {
+                Material::Conductor(Conductor::new(Vec3::new(eta), Vec3::new(k), roughness))
+            }
+            map::Material::RoughDielectric { ri, roughness } => {
+                Material::RoughDielectric(RoughDielectric::new(ri, roughness))
+            }
+            map::Material::Principled {
+                color,
+                metallic,
+                roughness,
+                specular,
+                transmission,
+                ior,
+                clearcoat,
+                clearcoat_roughness,
+                sheen,
+                emission,
+            } => Material::Principled(
+                Principled::new(Vec3::new(color))
+                    .with_metallic(metallic)
+                    .with_roughness(roughness)
+                    .with_specular(specular)
+                    .with_transmission(transmission, ior)
+                    .with_clearcoat(clearcoat, clearcoat_roughness)
+                    .with_sheen(sheen)
+                    .with_emission(Vec3::new(emission)),
+            ),
+        }
+    }
+}
+
+impl From<map::Density> for Density {
+    fn from(density: map::Density) -> Self {
+        match density {
+            map::Density::Grid {
+                min,
+                max,
+                size,
+                values,
+            } => Density::Grid(Grid::new(Vec3::new(min), Vec3::new(max), size, values)),
+            map::Density::Noise {
+                density,
+                scale,
+                octaves,
+                threshold,
+                seed,
+            } => Density::Noise(
+                Noise::new(density, scale)
+                    .with_octaves(octaves)
+                    .with_threshold(threshold)
+                    .with_seed(seed),
+            ),
+        }
+    }
+}
+
+impl From<map::Phase> for phase::Basic {
+    fn from(phase: map::Phase) -> Self {
+        match phase {
+            map::Phase::Isotropic => phase::Basic::Isotropic(Isotropic),
+            map::Phase::HenyeyGreenstein { g } => {
+                phase::Basic::HenyeyGreenstein(HenyeyGreenstein::new(g))
             }
+            map::Phase::Rayleigh => phase::Basic::Rayleigh(Rayleigh),
+            map::Phase::DoubleHenyeyGreenstein {
+                forward,
+                backward,
+                weight,
+            } => phase::Basic::DoubleHenyeyGreenstein(DoubleHenyeyGreenstein::new(
+                forward, backward, weight,
+            )),
+            map::Phase::Mie => phase::Basic::DoubleHenyeyGreenstein(DoubleHenyeyGreenstein::mie()),
+        }
+    }
+}
+
+impl From<map::Dispersion> for Dispersion {
+    fn from(dispersion: map::Dispersion) -> Self {
+        match dispersion {
+            map::Dispersion::Cauchy { a, b } => Dispersion::Cauchy { a, b },
+            map::Dispersion::Sellmeier { b, c } => Dispersion::Sellmeier { b, c },
+            map::Dispersion::Bk7 => Dispersion::bk7(),
+            map::Dispersion::Diamond => Dispersion::diamond(),
         }
     }
 }
@@ -74,21 +654,96 @@ fn test() {
     println!(
         "{}",
         serde_yaml::to_string(&Scene {
+            camera: Default::default(),
+            render: Default::default(),
+            env: Env::Dark,
+            atmosphere: None,
             objects: vec![Object {
                 shape: Shape::Sphere {
                     center: [0.0, 10.0, 20.0],
                     radius: 100.0,
                 },
-                material: Material::Lambertian {
-                    color: [0.7, 0.0, 0.0],
+                material: Material::Checker {
+                    odd: Box::new(Material::Lambertian {
+                        color: [0.7, 0.0, 0.0],
+                    }),
+                    even: Box::new(Material::Metal {
+                        color: [0.7, 0.7, 0.7],
+                        fuzz: 0.1,
+                    }),
                 },
             }],
         })
         .unwrap()
     );
 
-    println!(
-        "{:?}",
-        serde_yaml::from_str::<Scene>(include_str!("../../scene.yml"))
-    )
+    let material = serde_yaml::from_str::<Material>("!principled {color: [1, 1, 1], metallic: 1}");
+    assert!(matches!(
+        material.unwrap(),
+        Material::Principled {
+            metallic: 1.0,
+            roughness: 0.5,
+            clearcoat: 0.0,
+            ..
+        }
+    ));
+
+    let material = serde_yaml::from_str::<Material>("!rough_dielectric {ri: 1.5}");
+    assert_eq!(
+        material.unwrap(),
+        Material::RoughDielectric {
+            ri: 1.5,
+            roughness: 0.0
+        }
+    );
+
+    let atmosphere = serde_yaml::from_str::<Atmosphere>(
+        "{density: 0.1, color: [1, 1, 1], falloff: 0.5, phase: !henyey_greenstein {g: 0.6}}",
+    );
+    assert_eq!(
+        atmosphere.unwrap(),
+        Atmosphere {
+            density: 0.1,
+            color: [1.0; 3],
+            base_height: 0.0,
+            falloff: 0.5,
+            phase: Some(Phase::HenyeyGreenstein { g: 0.6 }),
+        }
+    );
+
+    let render = serde_yaml::from_str::<Render>("{spectral: true, width: 320}").unwrap();
+    assert_eq!(
+        render,
+        Render {
+            spectral: true,
+            width: 320,
+            ..Default::default()
+        }
+    );
+
+    let scene = serde_yaml::from_str::<Scene>(include_str!("../../scene.yml")).unwrap();
+    assert_eq!(scene.env, Env::Default);
+    assert_eq!(scene.render.sample_per_pixel, 20);
+    assert_eq!(
+        scene.render.filter,
+        Filter::Mitchell {
+            radius: 2.0,
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0
+        }
+    );
+    assert_eq!(
+        scene.render.adaptive,
+        Some(Adaptive {
+            threshold: 0.02,
+            min_samples: 8,
+            max_samples: 4096
+        })
+    );
+
+    // Reading a directory fails at no location in it.
+    assert!(matches!(
+        load(std::env::temp_dir().to_str().unwrap()),
+        Err(Error::Invalid { .. })
+    ));
 }
@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use crate::{tone_mapping::ToneMapping, util::linear_to_srgb, vec3::Vec3, Error};
+
+/// Linear RGB pixels of a rendered image, row by row from the top.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameBuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec3>,
+}
+
+impl FrameBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        FrameBuffer {
+            width,
+            height,
+            pixels: vec![Vec3::ZERO; width * height],
+        }
+    }
+
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(usize, usize) -> Vec3) -> Self {
+        FrameBuffer {
+            width,
+            height,
+            pixels: (0..width * height)
+                .map(|i| f(i % width, i / width))
+                .collect(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> Vec3 {
+        self.pixels[x + y * self.width]
+    }
+
+    #[inline]
+    pub fn set(&mut self, x: usize, y: usize, color: Vec3) {
+        self.pixels[x + y * self.width] = color;
+    }
+
+    pub fn pixels(&self) -> &[Vec3] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Vec3] {
+        &mut self.pixels
+    }
+
+    pub fn map(&self, f: impl Fn(Vec3) -> Vec3) -> Self {
+        FrameBuffer {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|&c| f(c)).collect(),
+        }
+    }
+
+    pub fn tone_map(&self, tone_mapping: &ToneMapping) -> Self {
+        self.map(|c| tone_mapping.apply(c))
+    }
+
+    /// Encodes with the sRGB OETF and quantizes to 8 bits per channel, clamping values above 1.
+    /// Apply `tone_map` first to compress highlights instead of clipping them.
+    pub fn to_rgb8(&self) -> Vec<[u8; 3]> {
+        self.pixels
+            .iter()
+            .map(|&c| {
+                let c = linear_to_srgb(c);
+                [c.r(), c.g(), c.b()].map(|x| (x.clamp(0.0, 1.0) * 255.99).floor() as u8)
+            })
+            .collect()
+    }
+
+    /// Writes the image in the format given by the file extension:
+    /// `ppm`, `pfm`, `exr` or, with the `image` feature, `png`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        use crate::formats;
+
+        let path = path.as_ref();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("ppm") => formats::ppm::write(path, self),
+            Some("pfm") => formats::pfm::write(path, self),
+            Some("exr") => formats::exr::write(path, self),
+            #[cfg(feature = "image")]
+            Some("png") => formats::png::write(path, self),
+            _ => Err(Error::Io {
+                path: path.to_owned(),
+                source: std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "unsupported image format",
+                ),
+            }),
+        }
+    }
+}
@@ -0,0 +1,479 @@
+//! Iterative path tracing
+
+use std::ops::Deref;
+
+use rand::Rng;
+
+use crate::{
+    lights::Lights,
+    materials::{atmosphere::Atmosphere, Material, Medium, RayResult},
+    pdf::Pdf,
+    phase::PhasePdf,
+    ray::Ray,
+    render::Aov,
+    resolvers::Hit,
+    rng,
+    shapes::{HitRec, Shape},
+    spectrum,
+    vec3::Vec3,
+};
+
+/// Path tracer with next-event estimation and Russian roulette.
+///
+/// Vertices whose material returns a `pdf`, and scatterings in media, also sample a
+/// direction toward `lights`, and both strategies are weighted with the power heuristic.
+/// After `min_depth` bounces, paths are stopped at random with a probability growing as
+/// their throughput falls, which keeps the estimate unbiased.
+///
+/// In spectral mode, each path carries a wavelength that dispersive materials refract by.
+///
+/// An `atmosphere` fills the space out of the bounded media and of the closed objects that
+/// paths went into, like glass: light sampled from its scatterings draws shafts where
+/// lights shine through openings.
+pub struct PathTracer<'a, S: Shape> {
+    lights: &'a Lights<'a, S>,
+    /// Bounces traced before Russian roulette starts.
+    pub min_depth: u32,
+    /// Paths are cut after this many bounces.
+    pub max_depth: u32,
+    pub spectral: bool,
+    pub atmosphere: Option<Atmosphere>,
+}
+
+impl<'a, S: Shape> PathTracer<'a, S> {
+    pub fn new(lights: &'a Lights<'a, S>) -> Self {
+        PathTracer {
+            lights,
+            min_depth: 3,
+            max_depth: 64,
+            spectral: false,
+            atmosphere: None,
+        }
+    }
+
+    pub fn with_depth(self, min_depth: u32, max_depth: u32) -> Self {
+        PathTracer {
+            min_depth,
+            max_depth,
+            ..self
+        }
+    }
+
+    pub fn with_spectral(self, spectral: bool) -> Self {
+        PathTracer { spectral, ..self }
+    }
+
+    pub fn with_atmosphere(self, atmosphere: Option<Atmosphere>) -> Self {
+        PathTracer { atmosphere, ..self }
+    }
+
+    /// The medium a path is in: that of the material `medium`, or else the atmosphere when
+    /// the path is `outside` all objects.
+    fn medium<'b, M: Material>(
+        &'b self,
+        medium: Option<&'b M>,
+        outside: bool,
+    ) -> Option<&'b dyn Medium> {
+        let atmosphere = self.atmosphere.as_ref().filter(|_| outside);
+        medium
+            .and_then(|m| m.medium())
+            .or(atmosphere.map(|a| a as &dyn Medium))
+    }
+
+    pub fn sample<M: Material, DM: Deref<Target = M>>(
+        &self,
+        hit: impl Hit<DM>,
+        env: impl Fn(&Ray) -> Vec3,
+        ray: &Ray,
+    ) -> Vec3 {
+        let (direct, indirect) = self.trace(hit, env, ray, |_| {});
+        direct + indirect
+    }
+
+    /// Like `sample`, also returning the output variables of the first surface hit.
+    /// `material_id` numbers the materials, e.g. by their position in the scene.
+    pub fn sample_aov<M: Material, DM: Deref<Target = M>>(
+        &self,
+        hit: impl Hit<DM>,
+        env: impl Fn(&Ray) -> Vec3,
+        ray: &Ray,
+        material_id: impl Fn(&M) -> u32,
+    ) -> (Vec3, Aov) {
+        let mut aov = Aov::default();
+        let mut first = true;
+        let (direct, indirect) = self.trace(hit, env, ray, |vertex| {
+            if std::mem::take(&mut first) {
+                aov = Aov {
+                    albedo: vertex.result.albedo,
+                    normal: *vertex.hit_rec.normal.w(),
+                    depth: vertex.hit_rec.time * vertex.ray.direction.norm(),
+                    uv: vertex.hit_rec.uv,
+                    object: Some(vertex.hit_rec.object),
+                    material: Some(material_id(vertex.material)),
+                    ..aov
+                };
+            }
+        });
+        (
+            direct + indirect,
+            Aov {
+                direct,
+                indirect,
+                ..aov
+            },
+        )
+    }
+
+    /// Follows a path from `ray` and returns the light it carries, split into direct light,
+    /// which scattered at most once, and indirect light. Calls `on_vertex` on every surface
+    /// the path scatters on or stops at.
+    ///
+    /// Materials with a `medium` bound media the path scatters through by their phase
+    /// function, and that shadow rays cross with their transmittance. Crossing their
+    /// boundaries doesn't count as a bounce.
+    pub fn trace<M: Material, DM: Deref<Target = M>>(
+        &self,
+        hit: impl Hit<DM>,
+        env: impl Fn(&Ray) -> Vec3,
+        ray: &Ray,
+        mut on_vertex: impl FnMut(&Vertex<M>),
+    ) -> (Vec3, Vec3) {
+        let (mut direct, mut indirect) = (Vec3::ZERO, Vec3::ZERO);
+        // Light reaching a vertex at `depth` scattered `depth` times.
+        let mut add = |depth: u32, light: Vec3| {
+            if depth <= 1 {
+                direct = direct + light;
+            } else {
+                indirect = indirect + light;
+            }
+        };
+        let (mut ray, mut throughput) = if self.spectral {
+            let wavelength = spectrum::sample_wavelength();
+            (
+                ray.with_wavelength(Some(wavelength)),
+                spectrum::rgb_weight(wavelength),
+            )
+        } else {
+            (*ray, Vec3::new([1.0; 3]))
+        };
+        // Origin and density of `ray` when it was sampled from a BSDF or phase function, for
+        // the MIS weight of the light it may hit.
+        let mut scattered_from: Option<(Vec3, f64)> = None;
+        // The material of the medium the path is in.
+        let mut medium: Option<DM> = None;
+        // Closed surfaces the path went through into, the camera being outside of all.
+        let mut inside = 0u32;
+        let mut depth = 0;
+
+        while depth < self.max_depth {
+            rng::next_bounce();
+
+            let hit_rec = hit.hit(&ray);
+            let distance = hit_rec
+                .as_ref()
+                .map_or(f64::INFINITY, |(rec, _)| rec.time * ray.direction.norm());
+
+            if let Some(m) = self.medium(medium.as_deref(), inside == 0) {
+                if let Some(scatter_distance) = m.sample_distance(&ray, distance) {
+                    let location = ray.origin + *ray.direction.normalize() * scatter_distance;
+                    let pdf = PhasePdf::new(m.phase(), &ray.direction);
+                    add(
+                        depth + 1,
+                        throughput
+                            * self.sample_light(
+                                &hit,
+                                &ray,
+                                location,
+                                |scattered| m.color() * pdf.value(&scattered.direction),
+                                &pdf,
+                                Some(m),
+                                inside == 0,
+                            ),
+                    );
+
+                    // The phase function is sampled exactly: its value cancels out.
+                    let (direction, pdf_value) = pdf.generate_with_value();
+                    ray = Ray::new(location, direction).with_wavelength(ray.wavelength);
+                    throughput = throughput * m.color();
+                    scattered_from = Some((location, pdf_value));
+                    depth += 1;
+                    if !self.survive(depth, &mut throughput) {
+                        break;
+                    }
+                    continue;
+                }
+            }
+
+            let Some((rec, material)) = hit_rec else {
+                add(depth, throughput * env(&ray));
+                break;
+            };
+
+            if material.medium().is_some() {
+                medium = rec.front.then_some(material);
+                ray = Ray::new(rec.location, ray.direction).with_wavelength(ray.wavelength);
+                continue;
+            }
+
+            let HitRec {
+                location,
+                normal,
+                uv,
+                ..
+            } = rec;
+            let r = material.ray(&ray, &location, &normal, uv);
+            on_vertex(&Vertex {
+                depth,
+                ray: &ray,
+                hit_rec: &rec,
+                material: &*material,
+                result: &r,
+                throughput,
+            });
+            let emit_weight = match scattered_from {
+                Some((origin, pdf_value)) if r.emit != Vec3::ZERO => {
+                    let light_pdf = self
+                        .lights
+                        .light(rec.object)
+                        .map_or(0.0, |i| self.lights.light_value(i, origin, &ray.direction));
+                    power_heuristic(pdf_value, light_pdf)
+                }
+                _ => 1.0,
+            };
+            add(depth, throughput * r.emit * emit_weight);
+            let Some(scattered) = r.scattered else {
+                break;
+            };
+
+            if let Some(pdf) = &r.pdf {
+                add(
+                    depth + 1,
+                    throughput
+                        * self.sample_light(
+                            &hit,
+                            &ray,
+                            location,
+                            |scattered| material.bsdf(&ray, &normal.w(), scattered, r.albedo, uv),
+                            pdf,
+                            self.medium(medium.as_deref(), inside == 0),
+                            inside == 0,
+                        ),
+                );
+
+                let pdf_value = pdf.value(&scattered.direction);
+                if pdf_value <= 0.0 {
+                    break;
+                }
+                let bsdf = material.bsdf(&ray, &normal.w(), &scattered, r.albedo, uv);
+                throughput = throughput * bsdf / pdf_value;
+                scattered_from = Some((location, pdf_value));
+            } else {
+                throughput = throughput * r.albedo;
+                scattered_from = None;
+            }
+
+            // Going through a closed surface enters or leaves it.
+            let w = normal.w();
+            if !material.thin_walled() && ray.direction.dot(&w) * scattered.direction.dot(&w) > 0.0
+            {
+                inside = if rec.front {
+                    inside + 1
+                } else {
+                    inside.saturating_sub(1)
+                };
+            }
+            ray = scattered.with_wavelength(ray.wavelength);
+            depth += 1;
+            if !self.survive(depth, &mut throughput) {
+                break;
+            }
+        }
+        (direct, indirect)
+    }
+
+    /// Russian roulette after `depth` bounces. Scales up the throughput of surviving paths
+    /// to make up for the stopped ones.
+    fn survive(&self, depth: u32, throughput: &mut Vec3) -> bool {
+        if depth < self.min_depth {
+            return true;
+        }
+        let p = throughput
+            .x()
+            .max(throughput.y())
+            .max(throughput.z())
+            .min(0.95);
+        if rng::with(|rng| rng.gen::<f64>()) >= p {
+            return false;
+        }
+        *throughput = *throughput / p;
+        true
+    }
+
+    /// Light arriving from a direction sampled toward `lights`, times `scattering` toward it
+    /// (the BSDF, or the phase function) and the MIS weight, divided by the light pdf. The
+    /// shadow ray starts in `medium`, `outside` all objects or not, and crosses the
+    /// boundaries of media.
+    #[allow(clippy::too_many_arguments)]
+    fn sample_light<M: Material, DM: Deref<Target = M>>(
+        &self,
+        hit: &impl Hit<DM>,
+        ray: &Ray,
+        location: Vec3,
+        scattering: impl Fn(&Ray) -> Vec3,
+        pdf: &impl Pdf,
+        medium: Option<&dyn Medium>,
+        outside: bool,
+    ) -> Vec3 {
+        if self.lights.is_empty() {
+            return Vec3::ZERO;
+        }
+        let (sampled, direction) = self.lights.sample(location);
+        let light_pdf = self.lights.light_value(sampled, location, &direction);
+        let mut shadow_ray = Ray::new(location, direction).with_wavelength(ray.wavelength);
+        let bsdf = scattering(&shadow_ray);
+        if light_pdf <= 0.0 || bsdf == Vec3::ZERO {
+            return Vec3::ZERO;
+        }
+        let mut transmittance = 1.0;
+        // `medium` until the first boundary, then the one entered, if any, or the atmosphere.
+        let mut medium = medium;
+        let mut entered: Option<DM>;
+        loop {
+            let Some((rec, light)) = hit.hit(&shadow_ray) else {
+                return Vec3::ZERO;
+            };
+            if let Some(m) = medium {
+                let distance = rec.time * shadow_ray.direction.norm();
+                transmittance *= m.transmittance(&shadow_ray, distance);
+                if transmittance <= 0.0 {
+                    return Vec3::ZERO;
+                }
+            }
+            if light.medium().is_some() {
+                entered = rec.front.then_some(light);
+                medium = self.medium(entered.as_deref(), outside);
+                shadow_ray = Ray::new(rec.location, direction).with_wavelength(ray.wavelength);
+                continue;
+            }
+            // Another light in front hides the sampled one. Its light is counted by its own
+            // samples.
+            if self.lights.light(rec.object) != Some(sampled) {
+                return Vec3::ZERO;
+            }
+            let emit = light
+                .ray(&shadow_ray, &rec.location, &rec.normal, rec.uv)
+                .emit;
+            let weight = power_heuristic(light_pdf, pdf.value(&direction));
+            return emit * bsdf * (transmittance * weight / light_pdf);
+        }
+    }
+}
+
+/// A surface interaction along a path.
+pub struct Vertex<'a, M> {
+    /// Bounces before this vertex, 0 for the surface seen from the camera.
+    pub depth: u32,
+    pub ray: &'a Ray,
+    pub hit_rec: &'a HitRec,
+    pub material: &'a M,
+    pub result: &'a RayResult,
+    /// Throughput of the path up to this vertex.
+    pub throughput: Vec3,
+}
+
+/// Veach's power heuristic with an exponent of 2, for one sample from each strategy.
+pub fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0.0 {
+        0.0
+    } else {
+        a / (a + b)
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{
+        materials::{
+            constant_medium::ConstantMedium, Basic as BasicMaterial, Dielectric, Lambertian,
+        },
+        resolvers::linear_search::LinearSearch,
+        shapes::{Basic as BasicShape, Sphere},
+    };
+
+    // A convex diffuse object under a uniform sky reflects its albedo, with or without
+    // Russian roulette.
+    let objects = [(
+        BasicShape::Sphere(Sphere::new(Vec3::ZERO, 1.0)),
+        BasicMaterial::Lambertian(Lambertian::new(Vec3::new([0.5; 3]))),
+    )];
+    let scene = LinearSearch::new(objects.iter().map(|(s, m)| (s, m)));
+    let lights = Lights::from_objects(scene.objects());
+    let ray = Ray::new(Vec3::new([0.0, 0.0, 5.0]), Vec3::new([0.0, 0.0, -1.0]));
+    for path_tracer in [
+        PathTracer::new(&lights),
+        PathTracer::new(&lights).with_depth(0, 10),
+    ] {
+        rng::reseed(0);
+        let n = 20000;
+        let sum = (0..n)
+            .map(|_| path_tracer.sample(&scene, |_| Vec3::new([1.0; 3]), &ray))
+            .fold(Vec3::ZERO, |a, b| a + b);
+        assert!((sum.x() / n as f64 - 0.5).abs() < 0.02);
+    }
+    assert_eq!(power_heuristic(1.0, 1.0), 0.5);
+
+    // Hundreds of scatterings in a dense white medium don't grow the stack, and lose no light.
+    let objects = [(
+        BasicShape::Sphere(Sphere::new(Vec3::ZERO, 1.0)),
+        BasicMaterial::ConstantMedium(ConstantMedium::new(100.0, Vec3::new([1.0; 3]))),
+    )];
+    let scene = LinearSearch::new(objects.iter().map(|(s, m)| (s, m)));
+    let lights = Lights::from_objects(scene.objects());
+    // Counts the segments of a path.
+    struct Counted<'a, H>(&'a H, std::cell::Cell<u32>);
+    impl<R, H: Hit<R>> Hit<R> for Counted<'_, H> {
+        fn hit_with_range(&self, ray: &Ray, tmin: f64, tmax: f64) -> Option<(HitRec, R)> {
+            self.1.set(self.1.get() + 1);
+            self.0.hit_with_range(ray, tmin, tmax)
+        }
+    }
+    let path_tracer = PathTracer::new(&lights).with_depth(100_000, 100_000);
+    rng::reseed(0);
+    let mut longest = 0;
+    for _ in 0..100 {
+        let counted = Counted(&scene, std::cell::Cell::new(0));
+        let color = path_tracer.sample(&counted, |_| Vec3::new([1.0; 3]), &ray);
+        assert_eq!(color, Vec3::new([1.0; 3]));
+        longest = longest.max(counted.1.get());
+    }
+    // Most paths leave near where they entered, but some wander deep into the medium.
+    assert!(longest > 1000);
+
+    // Fog doesn't fill glass: looking up through a sphere, the sky is dimmed by the fog
+    // below and above it only.
+    let objects = [(
+        BasicShape::Sphere(Sphere::new(Vec3::ZERO, 1.0)),
+        BasicMaterial::Dielectric(Dielectric::new(1.5)),
+    )];
+    let scene = LinearSearch::new(objects.iter().map(|(s, m)| (s, m)));
+    let lights = Lights::from_objects(scene.objects());
+    // Black fog absorbs all the light it scatters.
+    let fog = Atmosphere::new(1.0, Vec3::ZERO).with_height_falloff(0.0, 1.0);
+    let up = Vec3::new([0.0, 1.0, 0.0]);
+    let ray = Ray::new(Vec3::new([0.0, -1.01, 0.0]), up);
+    let below = fog.transmittance(&ray, 0.01);
+    let above = fog.transmittance(&Ray::new(up, up), f64::INFINITY);
+    let sky = |ray: &Ray| Vec3::new([(ray.direction.y() > 0.0) as u8 as f64; 3]);
+    let mean = |path_tracer: PathTracer<BasicShape>, env: &dyn Fn(&Ray) -> Vec3| {
+        rng::reseed(0);
+        let n = 20000;
+        (0..n)
+            .map(|_| path_tracer.sample(&scene, env, &ray).x())
+            .sum::<f64>()
+            / n as f64
+    };
+    let foggy = mean(PathTracer::new(&lights).with_atmosphere(Some(fog)), &sky);
+    let clear = mean(PathTracer::new(&lights), &|ray| sky(ray) * above);
+    assert!((foggy - clear * below).abs() < 0.02);
+}
@@ -1,17 +1,29 @@
 pub mod bbox;
 pub mod camera;
+pub mod denoise;
 pub mod envs;
+pub mod error;
+pub mod filter;
 pub mod formats;
+pub mod framebuffer;
+pub mod integrator;
+pub mod lights;
 pub mod materials;
 pub mod matrix;
 pub mod onb;
 pub mod pdf;
+pub mod phase;
 pub mod primitives;
 pub mod ray;
 pub mod render;
 pub mod resolvers;
 pub mod rng;
 pub mod sample;
+pub mod samplers;
 pub mod shapes;
+pub mod spectrum;
+pub mod tone_mapping;
 pub mod util;
 pub mod vec3;
+
+pub use error::Error;
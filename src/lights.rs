@@ -0,0 +1,217 @@
+//! Emissive objects of a scene, for next-event estimation
+
+use std::{collections::HashMap, ops::Deref};
+
+use rand::Rng;
+
+use crate::{
+    materials::Material, ray::Ray, resolvers::object::Object, rng, shapes::Shape,
+    tone_mapping::luminance, vec3::Vec3,
+};
+
+/// The shapes of the emissive objects, selected in proportion to their power.
+pub struct Lights<'a, S: Shape> {
+    shapes: Vec<&'a S>,
+    /// Index of the light of each emissive object, by `HitRec::object`.
+    indices: HashMap<u32, usize>,
+    selection: AliasTable,
+}
+
+impl<'a, S: Shape> Lights<'a, S> {
+    /// Keeps the shapes whose material has a non-zero `emission`. Objects are identified by
+    /// their position in `objects`, as resolvers number them.
+    pub fn new<M: Material + 'a>(objects: impl Iterator<Item = (&'a S, &'a M)>) -> Self {
+        Self::with_ids(
+            objects
+                .enumerate()
+                .map(|(id, (shape, material))| (id as u32, shape, material)),
+        )
+    }
+
+    fn with_ids<M: Material + 'a>(objects: impl Iterator<Item = (u32, &'a S, &'a M)>) -> Self {
+        let mut indices = HashMap::new();
+        let (shapes, powers): (Vec<_>, Vec<_>) = objects
+            .filter(|(_, _, material)| material.emission() != Vec3::ZERO)
+            .enumerate()
+            .map(|(i, (id, shape, material))| {
+                indices.insert(id, i);
+                (shape, luminance(material.emission()) * shape.area())
+            })
+            .unzip();
+        Lights {
+            shapes,
+            indices,
+            selection: AliasTable::new(&powers),
+        }
+    }
+
+    /// Gathers the lights from the objects of a resolver, e.g. `LinearSearch::objects`.
+    pub fn from_objects<DS, M, DM>(objects: impl Iterator<Item = &'a Object<DS, DM>>) -> Self
+    where
+        DS: Deref<Target = S> + 'a,
+        M: Material + 'a,
+        DM: Deref<Target = M> + Clone + 'a,
+    {
+        Self::with_ids(objects.map(|object| (object.id(), &**object.shape(), &**object.material())))
+    }
+
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    /// Probability of selecting light `i`.
+    pub fn probability(&self, i: usize) -> f64 {
+        self.selection.probability(i)
+    }
+
+    /// The light of the object a `HitRec::object` refers to, if it's emissive.
+    pub fn light(&self, object: u32) -> Option<usize> {
+        self.indices.get(&object).copied()
+    }
+
+    /// Density of `direction` from `origin` when sampled with `generate`, in solid angle.
+    /// Intersects every light: when the light in that direction is known, `light_value` is
+    /// cheaper.
+    pub fn value(&self, origin: Vec3, direction: &Vec3) -> f64 {
+        (0..self.len())
+            .map(|i| self.light_value(i, origin, direction))
+            .sum()
+    }
+
+    /// Density of `direction` from `origin` when light `i` is selected and sampled, in solid
+    /// angle.
+    pub fn light_value(&self, i: usize, origin: Vec3, direction: &Vec3) -> f64 {
+        self.probability(i) * self.shapes[i].pdf_value(Ray::new(origin, *direction))
+    }
+
+    /// Selects a light and a direction from `origin` toward a point on it.
+    pub fn sample(&self, origin: Vec3) -> (usize, Vec3) {
+        let i = rng::with(|rng| self.selection.sample(rng.gen(), rng.gen()));
+        (i, self.shapes[i].random(&origin))
+    }
+
+    /// A direction from `origin` toward a point on a light.
+    pub fn generate(&self, origin: Vec3) -> Vec3 {
+        self.sample(origin).1
+    }
+}
+
+/// Samples indexes in proportion to weights in constant time (Vose's alias method).
+/// Falls back to uniform selection when all the weights are 0.
+#[derive(Clone, Debug)]
+pub struct AliasTable {
+    /// Probability of keeping the drawn bucket instead of taking its alias.
+    keep: Vec<f64>,
+    alias: Vec<usize>,
+    probabilities: Vec<f64>,
+}
+
+impl AliasTable {
+    pub fn new(weights: &[f64]) -> Self {
+        let n = weights.len();
+        let total: f64 = weights.iter().sum();
+        let probabilities: Vec<f64> = if total > 0.0 {
+            weights.iter().map(|w| w / total).collect()
+        } else {
+            vec![1.0 / n as f64; n]
+        };
+
+        let mut keep: Vec<f64> = probabilities.iter().map(|p| p * n as f64).collect();
+        let mut alias = vec![0; n];
+        let (mut small, mut large): (Vec<_>, Vec<_>) = (0..n).partition(|&i| keep[i] < 1.0);
+        while let (Some(s), Some(&l)) = (small.pop(), large.last()) {
+            alias[s] = l;
+            keep[l] -= 1.0 - keep[s];
+            if keep[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // Leftovers are 1 up to rounding errors.
+        for i in small.into_iter().chain(large) {
+            keep[i] = 1.0;
+        }
+
+        AliasTable {
+            keep,
+            alias,
+            probabilities,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keep.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keep.is_empty()
+    }
+
+    pub fn probability(&self, i: usize) -> f64 {
+        self.probabilities[i]
+    }
+
+    /// Maps two uniform numbers in [0, 1) to an index.
+    pub fn sample(&self, u: f64, v: f64) -> usize {
+        let i = ((u * self.len() as f64) as usize).min(self.len() - 1);
+        if v < self.keep[i] {
+            i
+        } else {
+            self.alias[i]
+        }
+    }
+}
+
+#[test]
+fn test() {
+    let weights = [1.0, 0.0, 3.0, 4.0];
+    let table = AliasTable::new(&weights);
+    let n = 100;
+    let mut counts = [0; 4];
+    for i in 0..n {
+        for j in 0..n {
+            counts[table.sample((i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64)] += 1;
+        }
+    }
+    for i in 0..4 {
+        assert!((table.probability(i) - weights[i] / 8.0).abs() < 1e-12);
+        assert!((counts[i] as f64 / (n * n) as f64 - table.probability(i)).abs() < 0.01);
+    }
+    assert_eq!(AliasTable::new(&[0.0, 0.0]).probability(1), 0.5);
+
+    use crate::{
+        materials::{Basic, DiffuseLight, Lambertian},
+        shapes::Sphere,
+    };
+    let objects = [
+        (
+            Sphere::new(Vec3::new([0.0, 0.0, -4.0]), 1.0),
+            Basic::DiffuseLight(DiffuseLight::new(Vec3::new([1.0; 3]))),
+        ),
+        (
+            Sphere::new(Vec3::new([0.0, 0.0, 4.0]), 1.0),
+            Basic::Lambertian(Lambertian::new(Vec3::new([0.5; 3]))),
+        ),
+        (
+            Sphere::new(Vec3::new([0.0, 0.0, 8.0]), 1.0),
+            Basic::DiffuseLight(DiffuseLight::new(Vec3::new([3.0; 3]))),
+        ),
+    ];
+    let lights = Lights::new(objects.iter().map(|(s, m)| (s, m)));
+    assert_eq!(
+        [0, 1, 2].map(|object| lights.light(object)),
+        [Some(0), None, Some(1)]
+    );
+    // Each direction sees one light, whose density is all of it.
+    for direction in [Vec3::new([0.0, 0.0, -1.0]), Vec3::new([0.0, 0.0, 1.0])] {
+        let value = lights.value(Vec3::ZERO, &direction);
+        let light = if direction.z() < 0.0 { 0 } else { 1 };
+        assert!(value > 0.0);
+        assert_eq!(lights.light_value(light, Vec3::ZERO, &direction), value);
+        assert_eq!(lights.light_value(1 - light, Vec3::ZERO, &direction), 0.0);
+    }
+}
@@ -0,0 +1,134 @@
+//! A medium filling the whole scene, like fog or haze
+//!
+//! Its density falls off exponentially with the height, from `density` at `base_height`,
+//! and is uniform without a falloff. Free paths and transmittances are sampled from the
+//! closed form of the optical depth along a ray, so paths leaving upward through thin fog
+//! may escape it to the environment.
+
+use rand::Rng;
+
+use crate::{phase, ray::Ray, rng, vec3::Vec3};
+
+use super::Medium;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Atmosphere {
+    density: f64,
+    color: Vec3,
+    base_height: f64,
+    /// Inverse of the height over which the density falls by a factor of e.
+    falloff: f64,
+    phase: phase::Basic,
+}
+
+impl Atmosphere {
+    /// A uniform fog.
+    pub fn new(density: f64, color: Vec3) -> Self {
+        Atmosphere {
+            density,
+            color,
+            base_height: 0.0,
+            falloff: 0.0,
+            phase: phase::Basic::default(),
+        }
+    }
+
+    /// Exponential height fog, of `density` at `base_height`.
+    pub fn with_height_falloff(self, base_height: f64, falloff: f64) -> Self {
+        Atmosphere {
+            base_height,
+            falloff: falloff.max(0.0),
+            ..self
+        }
+    }
+
+    pub fn with_phase(self, phase: phase::Basic) -> Self {
+        Atmosphere { phase, ..self }
+    }
+
+    /// Density at the origin of `ray`, and its rate of decrease along the ray.
+    fn density_along(&self, ray: &Ray) -> (f64, f64) {
+        let height = ray.origin.y() - self.base_height;
+        (
+            self.density * (-self.falloff * height).exp(),
+            self.falloff * ray.direction.normalize().y(),
+        )
+    }
+
+    fn optical_depth(&self, ray: &Ray, distance: f64) -> f64 {
+        let (density, rate) = self.density_along(ray);
+        if density <= 0.0 {
+            0.0
+        } else if rate.abs() < 1e-9 {
+            density * distance
+        } else {
+            density * -(-rate * distance).exp_m1() / rate
+        }
+    }
+}
+
+impl Medium for Atmosphere {
+    fn sample_distance(&self, ray: &Ray, max_distance: f64) -> Option<f64> {
+        let (density, rate) = self.density_along(ray);
+        if density <= 0.0 {
+            return None;
+        }
+        // Inverts the optical depth at a depth of unit exponential distribution.
+        let depth = -(1.0 - rng::with(|rng| rng.gen::<f64>())).ln();
+        let distance = if rate.abs() < 1e-9 {
+            depth / density
+        } else {
+            let x = 1.0 - depth * rate / density;
+            if x <= 0.0 {
+                // Beyond the optical depth of the whole ray.
+                return None;
+            }
+            -x.ln() / rate
+        };
+        (distance < max_distance).then_some(distance)
+    }
+
+    fn transmittance(&self, ray: &Ray, distance: f64) -> f64 {
+        (-self.optical_depth(ray, distance)).exp()
+    }
+
+    fn color(&self) -> Vec3 {
+        self.color
+    }
+
+    fn phase(&self) -> phase::Basic {
+        self.phase
+    }
+}
+
+#[test]
+fn test() {
+    let fog = Atmosphere::new(0.5, Vec3::new([1.0; 3]));
+    let ray = Ray::new(Vec3::ZERO, Vec3::new([3.0, 0.0, 0.0]));
+    assert!((fog.transmittance(&ray, 2.0) - (-1.0f64).exp()).abs() < 1e-9);
+
+    let fog = fog.with_height_falloff(1.0, 2.0);
+    rng::reseed(0);
+    let n = 100000;
+    for (ray, distance) in [
+        (Ray::new(Vec3::ZERO, Vec3::new([0.0, 1.0, 1.0])), 3.0),
+        (
+            Ray::new(Vec3::ZERO, Vec3::new([0.0, 1.0, 0.0])),
+            f64::INFINITY,
+        ),
+        (
+            Ray::new(Vec3::new([0.0, 2.0, 0.0]), Vec3::new([1.0, -1.0, 0.0])),
+            2.0,
+        ),
+    ] {
+        // Paths are unscattered in proportion to the transmittance.
+        let unscattered = (0..n)
+            .filter(|_| fog.sample_distance(&ray, distance).is_none())
+            .count() as f64
+            / n as f64;
+        assert!((unscattered - fog.transmittance(&ray, distance)).abs() < 0.005);
+    }
+    // Looking up, the sky is seen through an optical depth of density * e^falloff / falloff.
+    let up = Ray::new(Vec3::ZERO, Vec3::new([0.0, 1.0, 0.0]));
+    assert!((fog.transmittance(&up, f64::INFINITY) - (-0.25 * 2.0f64.exp()).exp()).abs() < 1e-9);
+}
@@ -1,4 +1,8 @@
-use crate::{onb::Onb, ray::Ray, vec3::Vec3};
+use crate::{
+    onb::Onb,
+    ray::Ray,
+    vec3::{NormVec3, Vec3},
+};
 
 use super::{Material, RayResult};
 
@@ -12,15 +16,55 @@ impl<T: Material> Checker<T> {
     pub fn new(odd: Box<T>, even: Box<T>) -> Self {
         Checker { odd, even }
     }
-}
 
-impl<T: Material> Material for Checker<T> {
-    fn ray(&self, ray: &Ray, location: &Vec3, normal: &Onb, uv: [f64; 2]) -> RayResult {
-        let [u, v] = uv;
+    /// The material of the square at `uv`.
+    fn square(&self, [u, v]: [f64; 2]) -> &T {
         if ((u * 10.0).floor() as i32 + (v * 10.0).floor() as i32) % 2 == 0 {
-            self.even.ray(ray, location, normal, uv)
+            &self.even
         } else {
-            self.odd.ray(ray, location, normal, uv)
+            &self.odd
         }
     }
 }
+
+impl<T: Material> Material for Checker<T> {
+    fn ray(&self, ray: &Ray, location: &Vec3, normal: &Onb, uv: [f64; 2]) -> RayResult {
+        self.square(uv).ray(ray, location, normal, uv)
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, normal: &NormVec3, scattered: &Ray, uv: [f64; 2]) -> f64 {
+        self.square(uv).scattering_pdf(ray, normal, scattered, uv)
+    }
+
+    fn bsdf(
+        &self,
+        ray: &Ray,
+        normal: &NormVec3,
+        scattered: &Ray,
+        albedo: Vec3,
+        uv: [f64; 2],
+    ) -> Vec3 {
+        self.square(uv).bsdf(ray, normal, scattered, albedo, uv)
+    }
+
+    fn emission(&self) -> Vec3 {
+        (self.odd.emission() + self.even.emission()) * 0.5
+    }
+}
+
+#[test]
+fn test() {
+    use super::{Basic, Lambertian, Metal};
+
+    let checker = Checker::new(
+        Box::new(Basic::Lambertian(Lambertian::new(Vec3::new([0.5; 3])))),
+        Box::new(Basic::Metal(Metal::new(Vec3::new([0.5; 3]), 0.0))),
+    );
+    let normal = Vec3::new([0.0, 0.0, 1.0]).normalize();
+    let ray = Ray::new(Vec3::new([0.0, 0.0, 1.0]), Vec3::new([0.0, 0.0, -1.0]));
+    let scattered = Ray::new(Vec3::ZERO, Vec3::new([0.0, 0.0, 1.0]));
+    // Only the Lambertian squares have a density.
+    let pdf = |uv| checker.scattering_pdf(&ray, &normal, &scattered, uv);
+    assert_eq!(pdf([0.15, 0.05]), 1.0 / std::f64::consts::PI);
+    assert_eq!(pdf([0.05, 0.05]), 0.0);
+}
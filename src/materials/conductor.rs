@@ -0,0 +1,85 @@
+use crate::{
+    onb::Onb,
+    ray::Ray,
+    vec3::{NormVec3, Vec3},
+};
+
+use super::{
+    microfacet::{Bsdf, Ggx, Interface, Microfacet},
+    Material, RayResult,
+};
+
+/// A rough metal, tinted by the complex index of refraction `eta + i k` of each channel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conductor {
+    eta: Vec3,
+    k: Vec3,
+    ggx: Ggx,
+}
+
+impl Conductor {
+    pub fn new(eta: Vec3, k: Vec3, roughness: f64) -> Self {
+        Conductor {
+            eta,
+            k,
+            ggx: Ggx::new(roughness),
+        }
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Self::new(
+            Vec3::new([0.143, 0.374, 1.442]),
+            Vec3::new([3.983, 2.386, 1.603]),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Self::new(
+            Vec3::new([0.155, 0.117, 0.138]),
+            Vec3::new([4.828, 3.122, 2.147]),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self::new(
+            Vec3::new([0.200, 0.924, 1.102]),
+            Vec3::new([3.912, 2.452, 2.142]),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Self::new(
+            Vec3::new([1.657, 0.880, 0.521]),
+            Vec3::new([9.224, 6.270, 4.837]),
+            roughness,
+        )
+    }
+
+    fn microfacet(&self, ray: &Ray, normal: &NormVec3) -> Microfacet {
+        let interface = Interface::Conductor {
+            eta: self.eta,
+            k: self.k,
+        };
+        Microfacet::new(ray, normal, self.ggx, interface)
+    }
+}
+
+impl Material for Conductor {
+    fn ray(&self, ray: &Ray, location: &Vec3, normal: &Onb, _uv: [f64; 2]) -> RayResult {
+        self.microfacet(ray, &normal.w()).scatter(location)
+    }
+
+    fn bsdf(
+        &self,
+        ray: &Ray,
+        normal: &NormVec3,
+        scattered: &Ray,
+        _albedo: Vec3,
+        _uv: [f64; 2],
+    ) -> Vec3 {
+        self.microfacet(ray, normal).eval(&scattered.direction)
+    }
+}
@@ -1,11 +1,19 @@
-use crate::{onb::Onb, ray::Ray, rng, vec3::Vec3};
+use crate::{
+    onb::Onb,
+    pdf::Pdf,
+    phase::{self, PhasePdf},
+    ray::Ray,
+    sample::make_scatter_distance,
+    vec3::Vec3,
+};
 
-use super::{Material, RayResult};
+use super::{Material, Medium, RayResult};
 
 #[derive(Clone)]
 pub struct ConstantMedium {
     neg_inv_density: f64,
     color: Vec3,
+    phase: phase::Basic,
 }
 
 impl ConstantMedium {
@@ -13,24 +21,51 @@ impl ConstantMedium {
         ConstantMedium {
             neg_inv_density: 1.0 / boundary,
             color,
+            phase: phase::Basic::default(),
         }
     }
+
+    pub fn with_phase(self, phase: phase::Basic) -> ConstantMedium {
+        ConstantMedium { phase, ..self }
+    }
 }
 
 impl Material for ConstantMedium {
-    fn ray(&self, _ray: &Ray, location: &Vec3, _normal: &Onb, _uv: [f64; 2]) -> RayResult {
+    fn ray(&self, ray: &Ray, location: &Vec3, _normal: &Onb, _uv: [f64; 2]) -> RayResult {
         RayResult {
             emit: Vec3::ZERO,
             albedo: self.color.clone(),
             scattered: Some(Ray::new(
                 *location,
-                *rng::with(|rng| Vec3::random_unit_vector(rng)),
+                PhasePdf::new(self.phase, &ray.direction).generate(),
             )),
             pdf: None,
         }
     }
 
-    fn volume(&self) -> Option<(f64, Vec3)> {
-        Some((self.neg_inv_density, self.color))
+    fn volume(&self) -> Option<(f64, Vec3, phase::Basic)> {
+        Some((self.neg_inv_density, self.color, self.phase))
+    }
+
+    fn medium(&self) -> Option<&dyn Medium> {
+        Some(self)
+    }
+}
+
+impl Medium for ConstantMedium {
+    fn sample_distance(&self, _ray: &Ray, max_distance: f64) -> Option<f64> {
+        Some(make_scatter_distance(self.neg_inv_density)).filter(|&t| t < max_distance)
+    }
+
+    fn transmittance(&self, _ray: &Ray, distance: f64) -> f64 {
+        (-distance / self.neg_inv_density).exp()
+    }
+
+    fn color(&self) -> Vec3 {
+        self.color
+    }
+
+    fn phase(&self) -> phase::Basic {
+        self.phase
     }
 }
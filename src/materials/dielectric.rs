@@ -2,54 +2,163 @@ use rand::Rng;
 
 use crate::{onb::Onb, ray::Ray, rng, vec3::Vec3};
 
-use super::{Material, RayResult};
+use super::{microfacet::fresnel_dielectric, Material, RayResult};
 
 #[derive(Clone)]
 pub struct Dielectric {
     ri: f64,
+    dispersion: Option<Dispersion>,
+    /// Color of the light after travelling a distance inside.
+    absorption: Option<(Vec3, f64)>,
+    thin_walled: bool,
 }
 
 impl Dielectric {
     pub fn new(ri: f64) -> Dielectric {
-        Dielectric { ri }
+        Dielectric {
+            ri,
+            dispersion: None,
+            absorption: None,
+            thin_walled: false,
+        }
+    }
+
+    /// Tints the light to `color` once it travelled `distance` inside, by the Beer-Lambert
+    /// law. The distance is measured from where the ray entered, so nothing else should
+    /// be inside.
+    pub fn with_absorption(self, color: Vec3, distance: f64) -> Dielectric {
+        Dielectric {
+            absorption: Some((color, distance)),
+            ..self
+        }
+    }
+
+    /// Makes the surface a wall thin enough for light to go through unbent, like a window
+    /// pane or a soap bubble. Absorption then tints the light crossing it once by `color`.
+    pub fn with_thin_walled(self, thin_walled: bool) -> Dielectric {
+        Dielectric {
+            thin_walled,
+            ..self
+        }
+    }
+
+    /// Spectral rays see the index of refraction of their wavelength, the others `ri`.
+    pub fn with_dispersion(self, dispersion: Dispersion) -> Dielectric {
+        Dielectric {
+            dispersion: Some(dispersion),
+            ..self
+        }
+    }
+
+    fn ri(&self, ray: &Ray) -> f64 {
+        match (&self.dispersion, ray.wavelength) {
+            (Some(dispersion), Some(wavelength)) => dispersion.ri(wavelength),
+            _ => self.ri,
+        }
+    }
+
+    /// Color of the light after travelling `distance` inside.
+    fn transmittance(&self, distance: f64) -> Vec3 {
+        let Some((color, d)) = self.absorption else {
+            return Vec3::new([1.0; 3]);
+        };
+        let t = distance / d;
+        Vec3::new([color.r().powf(t), color.g().powf(t), color.b().powf(t)])
+    }
+}
+
+/// Index of refraction as a function of the wavelength.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dispersion {
+    /// `a + b / λ²`, λ in micrometers.
+    Cauchy { a: f64, b: f64 },
+    /// `sqrt(1 + Σ b λ² / (λ² - c))`, λ in micrometers.
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    /// Borosilicate crown glass.
+    pub fn bk7() -> Self {
+        Dispersion::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        }
+    }
+
+    pub fn diamond() -> Self {
+        Dispersion::Sellmeier {
+            b: [4.3356, 0.3306, 0.0],
+            c: [0.1060f64.powi(2), 0.1750f64.powi(2), 0.0],
+        }
+    }
+
+    /// `wavelength` is in nanometers.
+    pub fn ri(&self, wavelength: f64) -> f64 {
+        let l2 = (wavelength / 1000.0).powi(2);
+        match *self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f64 = b.iter().zip(c).map(|(b, c)| b * l2 / (l2 - c)).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
     }
 }
 
 impl Material for Dielectric {
     fn ray(&self, ray: &Ray, location: &Vec3, normal: &Onb, _uv: [f64; 2]) -> RayResult {
         let normal = normal.w();
-        let b: Vec3 = -(ray.direction.dot(&*normal)) * *normal;
-        let reflected: Vec3 = ray.direction + 2.0 * b;
-
-        let (outward_normal, ni_over_nt, cosine) = if ray.direction.dot(&*normal) > 0.0 {
-            (
-                -*normal,
-                self.ri,
-                self.ri * ray.direction.dot(&*normal) / ray.direction.norm(),
-            )
-        } else {
-            (
-                *normal,
-                self.ri.recip(),
-                -ray.direction.dot(&*normal) / ray.direction.norm(),
-            )
-        };
+        let ri = self.ri(ray);
+        let direction = *ray.direction.normalize();
+        let cosine = direction.dot(&normal);
+        let reflected = direction - *normal * (2.0 * cosine);
+        let exiting = cosine > 0.0;
+        let reflect = |reflectance: f64| rng::with(|rng| rng.gen_range(0.0..1.0)) < reflectance;
 
-        let v = match refract(&-ray.direction, &outward_normal, ni_over_nt) {
-            Option::Some(ref refracted)
-                if rng::with(|rng| rng.gen_range(0.0..1.0)) >= schlick(cosine, self.ri) =>
-            {
-                *refracted
+        let (v, albedo) = if self.thin_walled {
+            // Light bouncing between the two faces adds up to this reflectance.
+            let r = fresnel_dielectric(cosine.abs(), ri);
+            if reflect(2.0 * r / (1.0 + r)) {
+                (reflected, Vec3::new([1.0; 3]))
+            } else {
+                (
+                    direction,
+                    self.transmittance(self.absorption.map_or(1.0, |(_, d)| d)),
+                )
             }
-            _ => reflected,
+        } else {
+            // The normal on the side of the ray and the ratio of the indices of refraction.
+            let (outward_normal, ni_over_nt) = if exiting {
+                (-*normal, ri)
+            } else {
+                (*normal, ri.recip())
+            };
+            let v = match refract(&-direction, &outward_normal, ni_over_nt) {
+                Some(refracted)
+                    if !reflect(fresnel_dielectric(cosine.abs(), ni_over_nt.recip())) =>
+                {
+                    refracted
+                }
+                _ => reflected,
+            };
+            let albedo = if exiting {
+                self.transmittance((*location - ray.origin).norm())
+            } else {
+                Vec3::new([1.0; 3])
+            };
+            (v, albedo)
         };
         RayResult {
             emit: Vec3::ZERO,
-            albedo: Vec3::new([1.0; 3]),
+            albedo,
             scattered: Some(Ray::new(*location, v)),
             pdf: None,
         }
     }
+
+    fn thin_walled(&self) -> bool {
+        self.thin_walled
+    }
 }
 
 fn refract(v: &Vec3, n: &Vec3, ni_over_nt: f64) -> Option<Vec3> {
@@ -63,8 +172,42 @@ fn refract(v: &Vec3, n: &Vec3, ni_over_nt: f64) -> Option<Vec3> {
     }
 }
 
-#[inline(always)]
-fn schlick(cosine: f64, ri: f64) -> f64 {
-    let r0 = (1.0 - ri) / (1.0 + ri).powi(2);
-    r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
+#[test]
+fn test() {
+    assert!((Dispersion::bk7().ri(587.6) - 1.5168).abs() < 1e-4);
+    assert!((Dispersion::diamond().ri(589.3) - 2.417).abs() < 2e-3);
+    let cauchy = Dispersion::Cauchy { a: 1.5, b: 0.004 };
+    assert!((cauchy.ri(500.0) - 1.516).abs() < 1e-9);
+    assert!(Dispersion::bk7().ri(450.0) > Dispersion::bk7().ri(650.0));
+
+    let dielectric = Dielectric::new(1.3).with_dispersion(Dispersion::bk7());
+    let ray = Ray::new(Vec3::ZERO, Vec3::new([0.0, 0.0, -1.0]));
+    assert_eq!(dielectric.ri(&ray), 1.3);
+    let ray = ray.with_wavelength(Some(587.6));
+    assert!((dielectric.ri(&ray) - 1.5168).abs() < 1e-4);
+
+    // Glass 1 thick absorbing half the red, crossed over 2.
+    let tinted = Dielectric::new(1.5).with_absorption(Vec3::new([0.5, 1.0, 1.0]), 1.0);
+    let normal = Onb::from_w(Vec3::new([0.0, 0.0, 1.0]).normalize());
+    let inside = Ray::new(Vec3::new([0.0, 0.0, -2.0]), Vec3::new([0.0, 0.0, 1.0]));
+    let r = tinted.ray(&inside, &Vec3::ZERO, &normal, [0.0; 2]);
+    assert!((r.albedo - Vec3::new([0.25, 1.0, 1.0])).norm() < 1e-9);
+
+    // Light goes through a thin wall straight, or is reflected by both faces.
+    let thin = Dielectric::new(1.5).with_thin_walled(true);
+    let outside = Ray::new(Vec3::new([0.0, 0.0, 2.0]), Vec3::new([0.0, 0.0, -1.0]));
+    rng::reseed(0);
+    let n = 100000;
+    let mut reflected = 0;
+    for _ in 0..n {
+        let scattered = thin.ray(&outside, &Vec3::ZERO, &normal, [0.0; 2]).scattered;
+        let direction = scattered.unwrap().direction;
+        if direction.z() > 0.0 {
+            reflected += 1;
+        } else {
+            assert_eq!(direction, outside.direction);
+        }
+    }
+    let reflectance = reflected as f64 / n as f64;
+    assert!((reflectance - 0.08 / 1.04).abs() < 0.003);
 }
@@ -22,4 +22,8 @@ impl Material for DiffuseLight {
             pdf: None,
         }
     }
+
+    fn emission(&self) -> Vec3 {
+        self.color
+    }
 }
@@ -0,0 +1,303 @@
+//! Media whose density varies in space, like smoke, clouds or fog banks
+//!
+//! Free paths are sampled by delta tracking and transmittances estimated by ratio tracking
+//! (Novák et al., "Monte Carlo Methods for Volumetric Light Transport Simulation", 2018):
+//! both take tentative collisions at the rate of a majorant of the density, and keep the
+//! fraction of them the actual density accounts for.
+
+use rand::Rng;
+
+use crate::{
+    onb::Onb,
+    pdf::Pdf,
+    phase::{self, PhasePdf},
+    ray::Ray,
+    rng,
+    samplers::mix_bits,
+    vec3::Vec3,
+};
+
+use super::{Material, Medium, RayResult};
+
+/// Density in world space.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Density {
+    Grid(Grid),
+    Noise(Noise),
+}
+
+impl Density {
+    pub fn at(&self, p: &Vec3) -> f64 {
+        match self {
+            Density::Grid(grid) => grid.at(p),
+            Density::Noise(noise) => noise.at(p),
+        }
+    }
+
+    /// Bound of the density everywhere.
+    pub fn majorant(&self) -> f64 {
+        match self {
+            Density::Grid(grid) => grid.max,
+            Density::Noise(noise) => noise.density,
+        }
+    }
+}
+
+/// Densities sampled at the vertices of a regular grid spanning a box, and interpolated
+/// trilinearly. The density is zero out of the box.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+    min: Vec3,
+    max_corner: Vec3,
+    size: [usize; 3],
+    values: Vec<f64>,
+    max: f64,
+}
+
+impl Grid {
+    /// `values` are ordered by x, then y, then z, and there are at least 2 per axis.
+    pub fn new(min: Vec3, max: Vec3, size: [usize; 3], values: Vec<f64>) -> Self {
+        assert!(size.iter().all(|&n| n >= 2));
+        assert_eq!(values.len(), size[0] * size[1] * size[2]);
+        Grid {
+            min,
+            max_corner: max,
+            size,
+            max: values.iter().copied().fold(0.0, f64::max),
+            values,
+        }
+    }
+
+    /// A grid of `size` sampling `f` at its vertices.
+    pub fn from_fn(min: Vec3, max: Vec3, size: [usize; 3], f: impl Fn(Vec3) -> f64) -> Self {
+        let mut values = Vec::with_capacity(size[0] * size[1] * size[2]);
+        for z in 0..size[2] {
+            for y in 0..size[1] {
+                for x in 0..size[0] {
+                    let t = |i: usize, n: usize| i as f64 / (n - 1) as f64;
+                    let d = max - min;
+                    values.push(f(min
+                        + Vec3::new([
+                            d.x() * t(x, size[0]),
+                            d.y() * t(y, size[1]),
+                            d.z() * t(z, size[2]),
+                        ])));
+                }
+            }
+        }
+        Self::new(min, max, size, values)
+    }
+
+    pub fn at(&self, p: &Vec3) -> f64 {
+        let d = self.max_corner - self.min;
+        let local = *p - self.min;
+        let mut cell = [0; 3];
+        let mut t = [0.0; 3];
+        for axis in 0..3 {
+            let x = [local.x(), local.y(), local.z()][axis] / [d.x(), d.y(), d.z()][axis];
+            if !(0.0..=1.0).contains(&x) {
+                return 0.0;
+            }
+            let x = x * (self.size[axis] - 1) as f64;
+            cell[axis] = (x as usize).min(self.size[axis] - 2);
+            t[axis] = x - cell[axis] as f64;
+        }
+        let value = |dx: usize, dy: usize, dz: usize| {
+            let [x, y, z] = [cell[0] + dx, cell[1] + dy, cell[2] + dz];
+            self.values[x + self.size[0] * (y + self.size[1] * z)]
+        };
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let plane = |dz: usize| {
+            lerp(
+                lerp(value(0, 0, dz), value(1, 0, dz), t[0]),
+                lerp(value(0, 1, dz), value(1, 1, dz), t[0]),
+                t[1],
+            )
+        };
+        lerp(plane(0), plane(1), t[2])
+    }
+}
+
+/// Fractal value noise, from 0 to `density`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Noise {
+    density: f64,
+    /// Size of the largest features.
+    scale: f64,
+    octaves: u32,
+    /// Noise below it is empty, making puffs out of the noise.
+    threshold: f64,
+    seed: u64,
+}
+
+impl Noise {
+    pub fn new(density: f64, scale: f64) -> Self {
+        Noise {
+            density,
+            scale,
+            octaves: 4,
+            threshold: 0.0,
+            seed: 0,
+        }
+    }
+
+    pub fn with_octaves(self, octaves: u32) -> Self {
+        Noise { octaves, ..self }
+    }
+
+    pub fn with_threshold(self, threshold: f64) -> Self {
+        Noise { threshold, ..self }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Noise { seed, ..self }
+    }
+
+    pub fn at(&self, p: &Vec3) -> f64 {
+        let (mut sum, mut weight) = (0.0, 0.0);
+        for octave in 0..self.octaves.max(1) {
+            let amplitude = 0.5f64.powi(octave as i32);
+            let frequency = 2.0f64.powi(octave as i32) / self.scale;
+            sum += amplitude * self.value_noise(*p * frequency, octave as u64);
+            weight += amplitude;
+        }
+        let noise = sum / weight;
+        self.density * ((noise - self.threshold) / (1.0 - self.threshold)).max(0.0)
+    }
+
+    /// Random values in [0, 1) at the integer lattice, interpolated smoothly.
+    fn value_noise(&self, p: Vec3, octave: u64) -> f64 {
+        let [x, y, z] = [p.x(), p.y(), p.z()];
+        let (fx, fy, fz) = (x.floor(), y.floor(), z.floor());
+        let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+        let (tx, ty, tz) = (smooth(x - fx), smooth(y - fy), smooth(z - fz));
+        let value = |dx: f64, dy: f64, dz: f64| {
+            let hash = [fx + dx, fy + dy, fz + dz]
+                .iter()
+                .fold(mix_bits(self.seed ^ octave), |h, &c| {
+                    mix_bits(h ^ (c as i64 as u64))
+                });
+            (hash >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let plane = |dz: f64| {
+            lerp(
+                lerp(value(0.0, 0.0, dz), value(1.0, 0.0, dz), tx),
+                lerp(value(0.0, 1.0, dz), value(1.0, 1.0, dz), tx),
+                ty,
+            )
+        };
+        lerp(plane(0.0), plane(1.0), tz)
+    }
+}
+
+/// A medium of varying density filling the inside of its shape.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeterogeneousMedium {
+    density: Density,
+    color: Vec3,
+    phase: phase::Basic,
+}
+
+impl HeterogeneousMedium {
+    pub fn new(density: Density, color: Vec3) -> Self {
+        HeterogeneousMedium {
+            density,
+            color,
+            phase: phase::Basic::default(),
+        }
+    }
+
+    pub fn with_phase(self, phase: phase::Basic) -> Self {
+        HeterogeneousMedium { phase, ..self }
+    }
+
+    /// Distances to the tentative collisions, taken at the rate of the majorant, until
+    /// `max_distance`.
+    fn collisions(&self, max_distance: f64) -> impl Iterator<Item = f64> {
+        let majorant = self.density.majorant();
+        // Out of a closed boundary, they wouldn't end.
+        let end = majorant <= 0.0 || !max_distance.is_finite();
+        let mut t = 0.0;
+        std::iter::from_fn(move || {
+            if end {
+                return None;
+            }
+            t -= (1.0 - rng::with(|rng| rng.gen::<f64>())).ln() / majorant;
+            (t < max_distance).then_some(t)
+        })
+    }
+
+    fn density_at(&self, ray: &Ray, t: f64) -> f64 {
+        self.density
+            .at(&(ray.origin + *ray.direction.normalize() * t))
+    }
+}
+
+impl Medium for HeterogeneousMedium {
+    fn sample_distance(&self, ray: &Ray, max_distance: f64) -> Option<f64> {
+        let majorant = self.density.majorant();
+        self.collisions(max_distance)
+            .find(|&t| rng::with(|rng| rng.gen::<f64>()) * majorant < self.density_at(ray, t))
+    }
+
+    fn transmittance(&self, ray: &Ray, distance: f64) -> f64 {
+        let majorant = self.density.majorant();
+        self.collisions(distance)
+            .map(|t| 1.0 - self.density_at(ray, t) / majorant)
+            .product()
+    }
+
+    fn color(&self) -> Vec3 {
+        self.color
+    }
+
+    fn phase(&self) -> phase::Basic {
+        self.phase
+    }
+}
+
+impl Material for HeterogeneousMedium {
+    fn ray(&self, ray: &Ray, location: &Vec3, _normal: &Onb, _uv: [f64; 2]) -> RayResult {
+        RayResult {
+            emit: Vec3::ZERO,
+            albedo: self.color,
+            scattered: Some(Ray::new(
+                *location,
+                PhasePdf::new(self.phase, &ray.direction).generate(),
+            )),
+            pdf: None,
+        }
+    }
+
+    fn medium(&self) -> Option<&dyn Medium> {
+        Some(self)
+    }
+}
+
+#[test]
+fn test() {
+    // The density grows from 0 to 2 along x, for an optical depth of 1 across the box.
+    let grid = Grid::from_fn(Vec3::ZERO, Vec3::new([1.0; 3]), [5, 2, 2], |p| 2.0 * p.x());
+    assert!((grid.at(&Vec3::new([0.3, 0.5, 0.5])) - 0.6).abs() < 1e-9);
+    assert_eq!(grid.at(&Vec3::new([1.5, 0.5, 0.5])), 0.0);
+
+    let medium = HeterogeneousMedium::new(Density::Grid(grid), Vec3::new([1.0; 3]));
+    let ray = Ray::new(Vec3::new([0.0, 0.5, 0.5]), Vec3::new([2.0, 0.0, 0.0]));
+    rng::reseed(0);
+    let n = 100000;
+    let transmittance = (0..n).map(|_| medium.transmittance(&ray, 1.0)).sum::<f64>() / n as f64;
+    let unscattered = (0..n)
+        .filter(|_| medium.sample_distance(&ray, 1.0).is_none())
+        .count() as f64
+        / n as f64;
+    let expected = (-1.0f64).exp();
+    assert!((transmittance - expected).abs() < 0.005);
+    assert!((unscattered - expected).abs() < 0.005);
+
+    let noise = Noise::new(3.0, 0.5).with_threshold(0.4).with_seed(7);
+    for i in 0..100 {
+        let d = noise.at(&Vec3::new([i as f64 * 0.37, i as f64 * 0.11, -(i as f64)]));
+        assert!((0.0..3.0).contains(&d));
+    }
+}
@@ -28,7 +28,7 @@ impl Material for Lambertian {
             emit: Vec3::ZERO,
             albedo: self.albedo,
             scattered: Some(Ray::new(*location, direction)),
-            pdf: Some(CosinePdf::new(normal.w())),
+            pdf: Some(CosinePdf::new(normal.w()).into()),
         }
         // let direction = rng::with(|rng| Vec3::random_in_hemisphere(rng, normal)).normalize();
         // RayResult {
@@ -39,7 +39,7 @@ impl Material for Lambertian {
         // }
     }
 
-    fn scattering_pdf(&self, _ray: &Ray, normal: &NormVec3, scattered: &Ray) -> f64 {
+    fn scattering_pdf(&self, _ray: &Ray, normal: &NormVec3, scattered: &Ray, _uv: [f64; 2]) -> f64 {
         let cosine = normal.dot(&scattered.direction.normalize());
         if cosine < 0.0 {
             0.0
@@ -0,0 +1,332 @@
+//! GGX (Trowbridge-Reitz) microfacets with Smith masking
+//!
+//! Local directions are expressed in a frame whose `w` is the normal on the side of the
+//! outgoing direction `wo`, pointing back along the incoming ray.
+
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::{
+    materials::RayResult,
+    onb::Onb,
+    pdf::{self, Pdf},
+    ray::Ray,
+    rng,
+    vec3::{NormVec3, Vec3},
+};
+
+/// Distribution of the microfacet normals.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ggx {
+    alpha: f64,
+}
+
+impl Ggx {
+    /// `roughness` is perceptual, the width of the distribution being its square. It is
+    /// clamped to stay away from the perfect mirror, that only specular materials can render.
+    pub fn new(roughness: f64) -> Self {
+        Ggx {
+            alpha: roughness.powi(2).max(1e-3),
+        }
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// Density of the normal `m` by projected area.
+    pub fn d(&self, m: &Vec3) -> f64 {
+        if m.z() <= 0.0 {
+            return 0.0;
+        }
+        let a2 = self.alpha.powi(2);
+        a2 / (PI * (m.z().powi(2) * (a2 - 1.0) + 1.0).powi(2))
+    }
+
+    fn lambda(&self, w: &Vec3) -> f64 {
+        let cos2 = w.z().powi(2);
+        if cos2 == 0.0 {
+            return f64::INFINITY;
+        }
+        let tan2 = (1.0 - cos2) / cos2;
+        ((1.0 + self.alpha.powi(2) * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    /// Fraction of the microfacets visible from `w`.
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Fraction of the microfacets visible from both directions, height-correlated.
+    pub fn g2(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Samples a normal visible from `wo` (Heitz, "Sampling the GGX Distribution of Visible
+    /// Normals", 2018).
+    pub fn sample_visible(&self, wo: &Vec3, [u1, u2]: [f64; 2]) -> Vec3 {
+        let a = self.alpha;
+        let vh = *Vec3::new([a * wo.x(), a * wo.y(), wo.z()]).normalize();
+        let lensq = vh.x().powi(2) + vh.y().powi(2);
+        let t1 = if lensq > 0.0 {
+            Vec3::new([-vh.y(), vh.x(), 0.0]) / lensq.sqrt()
+        } else {
+            Vec3::new([1.0, 0.0, 0.0])
+        };
+        let t2 = vh.cross(&t1);
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1.powi(2)).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1.powi(2) - p2.powi(2)).max(0.0).sqrt();
+        *Vec3::new([a * nh.x(), a * nh.y(), nh.z().max(1e-9)]).normalize()
+    }
+
+    /// Density of `sample_visible`.
+    pub fn visible_pdf(&self, wo: &Vec3, m: &Vec3) -> f64 {
+        self.g1(wo) * wo.dot(m).max(0.0) * self.d(m) / wo.z()
+    }
+}
+
+/// Unpolarized reflectance of a dielectric interface, `eta` being the ratio of the indices of
+/// refraction of the far and near sides.
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let sin2_t = (1.0 - cos_i.powi(2)) / eta.powi(2);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (parallel.powi(2) + perpendicular.powi(2)) / 2.0
+}
+
+/// Unpolarized reflectance of a conductor of complex index of refraction `eta + i k`, per
+/// channel.
+pub fn fresnel_conductor(cos_i: f64, eta: Vec3, k: Vec3) -> Vec3 {
+    let f = |eta: f64, k: f64| {
+        let cos2 = cos_i.powi(2);
+        let sin2 = 1.0 - cos2;
+        let t0 = eta.powi(2) - k.powi(2) - sin2;
+        let a2_plus_b2 = (t0.powi(2) + 4.0 * eta.powi(2) * k.powi(2)).sqrt();
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let t2 = 2.0 * cos_i * a;
+        let rs = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2_plus_b2 + sin2.powi(2);
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        (rs + rp) / 2.0
+    };
+    Vec3::new([f(eta.r(), k.r()), f(eta.g(), k.g()), f(eta.b(), k.b())])
+}
+
+/// Schlick's approximation of the reflectance, `f0` being the one at normal incidence.
+pub fn fresnel_schlick(cos_i: f64, f0: Vec3) -> Vec3 {
+    f0 + (Vec3::new([1.0; 3]) - f0) * (1.0 - cos_i).clamp(0.0, 1.0).powi(5)
+}
+
+/// A BSDF that can be evaluated and importance sampled in any direction.
+pub trait Bsdf: Pdf + Copy + Into<pdf::Basic> {
+    /// The BSDF times the cosine between `direction` and the normal.
+    fn eval(&self, direction: &Vec3) -> Vec3;
+
+    /// Samples a direction from `location`, the albedo being the BSDF over its density.
+    fn scatter(self, location: &Vec3) -> RayResult {
+        let (direction, value) = self.generate_with_value();
+        let (albedo, scattered) = if value > 0.0 {
+            (
+                self.eval(&direction) / value,
+                Some(Ray::new(*location, direction)),
+            )
+        } else {
+            (Vec3::ZERO, None)
+        };
+        RayResult {
+            emit: Vec3::ZERO,
+            albedo,
+            scattered,
+            pdf: Some(self.into()),
+        }
+    }
+}
+
+/// Checks that `bsdf` reflects at most `max_albedo` of the light, that its density
+/// integrates to 1 and that sampling it agrees with integrating it uniformly.
+#[cfg(test)]
+pub(crate) fn check_bsdf(bsdf: &impl Bsdf, max_albedo: f64) {
+    rng::reseed(0);
+    let n = 200000;
+    // Integrals over the sphere, by importance sampling and uniformly.
+    let (mut albedo, mut uniform_albedo, mut pdf) = (Vec3::ZERO, Vec3::ZERO, 0.0);
+    for _ in 0..n {
+        let (direction, value) = bsdf.generate_with_value();
+        if value > 0.0 {
+            albedo = albedo + bsdf.eval(&direction) / value;
+        }
+        let direction = *rng::with(Vec3::random_unit_vector);
+        uniform_albedo = uniform_albedo + bsdf.eval(&direction) * (4.0 * PI);
+        pdf += bsdf.value(&direction) * 4.0 * PI;
+    }
+    let n = n as f64;
+    let (albedo, uniform_albedo, pdf) = (albedo / n, uniform_albedo / n, pdf / n);
+    assert!((0.9..1.02).contains(&pdf));
+    assert!(albedo.x() < max_albedo && albedo.y() < max_albedo && albedo.z() < max_albedo);
+    assert!((albedo - uniform_albedo).norm() < 0.03);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interface {
+    /// Reflects only, tinted by the complex Fresnel equations.
+    Conductor { eta: Vec3, k: Vec3 },
+    /// Reflects or refracts. `eta` is the index of refraction inside the surface.
+    Dielectric { eta: f64 },
+    /// Reflects only, with Schlick's approximation of the Fresnel equations from the
+    /// reflectance at normal incidence.
+    Schlick { f0: Vec3 },
+}
+
+/// Scattering of a ray by a rough interface: evaluates and samples the BSDF.
+#[derive(Clone, Copy, Debug)]
+pub struct Microfacet {
+    frame: Onb,
+    wo: Vec3,
+    ggx: Ggx,
+    interface: Interface,
+}
+
+impl Microfacet {
+    pub fn new(ray: &Ray, normal: &NormVec3, ggx: Ggx, interface: Interface) -> Self {
+        let wo = -*ray.direction.normalize();
+        let outside = wo.dot(normal) >= 0.0;
+        let frame = Onb::from_w(if outside { *normal } else { -*normal });
+        let interface = match interface {
+            Interface::Dielectric { eta } if !outside => Interface::Dielectric { eta: 1.0 / eta },
+            interface => interface,
+        };
+        Microfacet {
+            frame,
+            wo: frame.project(&wo),
+            ggx,
+            interface,
+        }
+    }
+
+    /// The microfacet normal refracting `wo` into `wi` and the Jacobian of the mapping from
+    /// `wi` to it.
+    fn transmission_normal(&self, wi: &Vec3, eta: f64) -> Option<(Vec3, f64)> {
+        let wo = self.wo;
+        let mut h = *(wo + *wi * eta).normalize();
+        if h.z() < 0.0 {
+            h = -h;
+        }
+        let (o_h, i_h) = (wo.dot(&h), wi.dot(&h));
+        if o_h <= 0.0 || i_h >= 0.0 {
+            return None;
+        }
+        Some((h, i_h.abs() / (i_h + o_h / eta).powi(2)))
+    }
+}
+
+impl Bsdf for Microfacet {
+    fn eval(&self, direction: &Vec3) -> Vec3 {
+        let (wo, wi) = (self.wo, *self.frame.project(direction).normalize());
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return Vec3::ZERO;
+        }
+        if wi.z() > 0.0 {
+            let h = *(wo + wi).normalize();
+            let f = match self.interface {
+                Interface::Conductor { eta, k } => fresnel_conductor(wo.dot(&h), eta, k),
+                Interface::Dielectric { eta } => {
+                    Vec3::new([fresnel_dielectric(wo.dot(&h), eta); 3])
+                }
+                Interface::Schlick { f0 } => fresnel_schlick(wo.dot(&h), f0),
+            };
+            return f * (self.ggx.d(&h) * self.ggx.g2(&wo, &wi) / (4.0 * wo.z()));
+        }
+        let Interface::Dielectric { eta } = self.interface else {
+            return Vec3::ZERO;
+        };
+        let Some((h, dh_dwi)) = self.transmission_normal(&wi, eta) else {
+            return Vec3::ZERO;
+        };
+        let t = 1.0 - fresnel_dielectric(wo.dot(&h), eta);
+        // Radiance is compressed into the narrower solid angle of the denser side.
+        let f = self.ggx.d(&h) * self.ggx.g2(&wo, &wi) * t * dh_dwi * wo.dot(&h)
+            / (wo.z() * eta.powi(2));
+        Vec3::new([f; 3])
+    }
+}
+
+impl Pdf for Microfacet {
+    fn value(&self, direction: &Vec3) -> f64 {
+        let (wo, wi) = (self.wo, *self.frame.project(direction).normalize());
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return 0.0;
+        }
+        if wi.z() > 0.0 {
+            let h = *(wo + wi).normalize();
+            let p = self.ggx.visible_pdf(&wo, &h) / (4.0 * wo.dot(&h));
+            return match self.interface {
+                Interface::Conductor { .. } | Interface::Schlick { .. } => p,
+                Interface::Dielectric { eta } => p * fresnel_dielectric(wo.dot(&h), eta),
+            };
+        }
+        let Interface::Dielectric { eta } = self.interface else {
+            return 0.0;
+        };
+        let Some((h, dh_dwi)) = self.transmission_normal(&wi, eta) else {
+            return 0.0;
+        };
+        self.ggx.visible_pdf(&wo, &h) * dh_dwi * (1.0 - fresnel_dielectric(wo.dot(&h), eta))
+    }
+
+    fn generate(&self) -> Vec3 {
+        let wo = self.wo;
+        let m = self
+            .ggx
+            .sample_visible(&wo, rng::with(|rng| [rng.gen(), rng.gen()]));
+        let cos_o = wo.dot(&m);
+        let reflected = m * (2.0 * cos_o) - wo;
+        let wi = match self.interface {
+            Interface::Conductor { .. } | Interface::Schlick { .. } => reflected,
+            Interface::Dielectric { eta } => {
+                let f = fresnel_dielectric(cos_o, eta);
+                if rng::with(|rng| rng.gen::<f64>()) < f {
+                    reflected
+                } else {
+                    // Total internal reflection has a reflectance of 1.
+                    let cos_t = (1.0 - (1.0 - cos_o.powi(2)) / eta.powi(2)).sqrt();
+                    m * (cos_o / eta - cos_t) - wo / eta
+                }
+            }
+        };
+        self.frame.local(wi)
+    }
+}
+
+#[test]
+fn test() {
+    let ray = Ray::new(Vec3::ZERO, Vec3::new([0.6, 0.0, -0.8]));
+    let normal = Vec3::new([0.0, 0.0, 1.0]).normalize();
+    for interface in [
+        Interface::Conductor {
+            eta: Vec3::new([0.2, 0.9, 1.1]),
+            k: Vec3::new([3.9, 2.5, 2.1]),
+        },
+        Interface::Dielectric { eta: 1.5 },
+    ] {
+        check_bsdf(
+            &Microfacet::new(&ray, &normal, Ggx::new(0.5), interface),
+            1.0,
+        );
+    }
+
+    assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-9);
+    assert_eq!(fresnel_dielectric(0.1, 1.0 / 1.5), 1.0);
+    let f = fresnel_conductor(1.0, Vec3::new([1.5; 3]), Vec3::ZERO);
+    assert!((f.x() - 0.04).abs() < 1e-9);
+}
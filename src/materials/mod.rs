@@ -1,9 +1,15 @@
+pub mod atmosphere;
 pub mod checker;
+pub mod conductor;
 pub mod constant_medium;
 pub mod dielectric;
 pub mod diffuse_light;
+pub mod heterogeneous_medium;
 pub mod lambertian;
 pub mod metal;
+pub mod microfacet;
+pub mod principled;
+pub mod rough_dielectric;
 pub mod tex;
 pub mod uv_map;
 pub mod wet_glass;
@@ -15,7 +21,7 @@ pub use metal::Metal;
 
 use crate::{
     onb::Onb,
-    pdf::CosinePdf,
+    pdf, phase,
     ray::Ray,
     vec3::{NormVec3, Vec3},
 };
@@ -24,18 +30,61 @@ pub struct RayResult {
     pub emit: Vec3,
     pub albedo: Vec3,
     pub scattered: Option<Ray>,
-    pub pdf: Option<CosinePdf>,
+    /// Density of `scattered`, for materials that can evaluate `bsdf` in any direction.
+    pub pdf: Option<pdf::Basic>,
 }
 
 pub trait Material {
     fn ray(&self, ray: &Ray, location: &Vec3, normal: &Onb, uv: [f64; 2]) -> RayResult;
-    fn volume(&self) -> Option<(f64, Vec3)> {
+    /// Negative inverse density, color and phase function of the homogeneous medium inside
+    /// the shape, for `sample::sample_with_volume`.
+    fn volume(&self) -> Option<(f64, Vec3, phase::Basic)> {
         None
     }
-    fn scattering_pdf(&self, ray: &Ray, normal: &NormVec3, scattered: &Ray) -> f64 {
-        let _ = (ray, normal, scattered);
+    /// The medium filling the inside of the shape, whose surface is then only its boundary.
+    fn medium(&self) -> Option<&dyn Medium> {
+        None
+    }
+    /// `uv` is that of the point `ray` hit, as given to `ray`.
+    fn scattering_pdf(&self, ray: &Ray, normal: &NormVec3, scattered: &Ray, uv: [f64; 2]) -> f64 {
+        let _ = (ray, normal, scattered, uv);
         0.0
     }
+    /// BSDF scattering `ray` into `scattered` times their cosine with the normal, for
+    /// materials returning a `pdf`. `albedo` is the one `ray` returned.
+    fn bsdf(
+        &self,
+        ray: &Ray,
+        normal: &NormVec3,
+        scattered: &Ray,
+        albedo: Vec3,
+        uv: [f64; 2],
+    ) -> Vec3 {
+        albedo * self.scattering_pdf(ray, normal, scattered, uv)
+    }
+    /// Average `emit` over the surface. Objects with a non-zero emission are the lights
+    /// sampled by next-event estimation.
+    fn emission(&self) -> Vec3 {
+        Vec3::ZERO
+    }
+    /// Whether the surface is a wall that paths going through don't stay inside of.
+    fn thin_walled(&self) -> bool {
+        false
+    }
+}
+
+/// A participating medium. Distances are along the normalized direction of the rays.
+pub trait Medium {
+    /// Distance to the next scattering along `ray`, if it's closer than `max_distance`.
+    fn sample_distance(&self, ray: &Ray, max_distance: f64) -> Option<f64>;
+    /// Fraction of the light travelling `distance` along `ray` unscattered, or an unbiased
+    /// estimate of it.
+    fn transmittance(&self, ray: &Ray, distance: f64) -> f64;
+    /// Albedo of a scattering.
+    fn color(&self) -> Vec3;
+    fn phase(&self) -> phase::Basic {
+        phase::Basic::default()
+    }
 }
 
 #[derive(Clone)]
@@ -46,7 +95,12 @@ pub enum Basic<'a> {
     Metal(Metal),
     Checker(checker::Checker<Basic<'a>>),
     ConstantMedium(constant_medium::ConstantMedium),
+    HeterogeneousMedium(heterogeneous_medium::HeterogeneousMedium),
     WetGlass(wet_glass::WetGlass),
+    Conductor(conductor::Conductor),
+    RoughDielectric(rough_dielectric::RoughDielectric),
+    Principled(principled::Principled),
+    Tex(tex::Tex),
     UvMap(
         uv_map::UvMap<&'a (dyn Fn(Ray, Vec3, Onb, [f32; 2]) -> (Vec3, Option<Ray>) + Send + Sync)>,
     ),
@@ -62,7 +116,12 @@ impl<'a> Basic<'a> {
             Basic::Metal(metal) => metal,
             Basic::Checker(checker) => checker,
             Basic::ConstantMedium(constant_medium) => constant_medium,
+            Basic::HeterogeneousMedium(heterogeneous_medium) => heterogeneous_medium,
             Basic::WetGlass(wet_glass) => wet_glass,
+            Basic::Conductor(conductor) => conductor,
+            Basic::RoughDielectric(rough_dielectric) => rough_dielectric,
+            Basic::Principled(principled) => principled,
+            Basic::Tex(tex) => tex,
             Basic::UvMap(uv_map) => uv_map,
         }
     }
@@ -73,11 +132,34 @@ impl<'a> Material for Basic<'a> {
         self.as_ref().ray(ray, location, normal, uv)
     }
 
-    fn volume(&self) -> Option<(f64, Vec3)> {
+    fn volume(&self) -> Option<(f64, Vec3, phase::Basic)> {
         self.as_ref().volume()
     }
 
-    fn scattering_pdf(&self, ray: &Ray, normal: &NormVec3, scattered: &Ray) -> f64 {
-        self.as_ref().scattering_pdf(ray, normal, scattered)
+    fn medium(&self) -> Option<&dyn Medium> {
+        self.as_ref().medium()
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, normal: &NormVec3, scattered: &Ray, uv: [f64; 2]) -> f64 {
+        self.as_ref().scattering_pdf(ray, normal, scattered, uv)
+    }
+
+    fn bsdf(
+        &self,
+        ray: &Ray,
+        normal: &NormVec3,
+        scattered: &Ray,
+        albedo: Vec3,
+        uv: [f64; 2],
+    ) -> Vec3 {
+        self.as_ref().bsdf(ray, normal, scattered, albedo, uv)
+    }
+
+    fn emission(&self) -> Vec3 {
+        self.as_ref().emission()
+    }
+
+    fn thin_walled(&self) -> bool {
+        self.as_ref().thin_walled()
     }
 }
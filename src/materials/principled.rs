@@ -0,0 +1,295 @@
+//! A principled material after Burley, "Physically-Based Shading at Disney", 2012
+//!
+//! Blends a diffuse base with sheen, a GGX specular layer tinted by the base color as it
+//! gets metallic, a rough glass and a clearcoat, sampling each lobe in proportion to its
+//! expected contribution.
+
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::{
+    onb::Onb,
+    pdf::Pdf,
+    ray::Ray,
+    rng,
+    tone_mapping::luminance,
+    vec3::{NormVec3, Vec3},
+};
+
+use super::{
+    microfacet::{fresnel_schlick, Bsdf, Ggx, Interface, Microfacet},
+    Material, RayResult,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Principled {
+    base_color: Vec3,
+    metallic: f64,
+    roughness: f64,
+    /// Reflectance of the dielectric base at normal incidence, 0.5 being 4%.
+    specular: f64,
+    transmission: f64,
+    ior: f64,
+    clearcoat: f64,
+    clearcoat_roughness: f64,
+    sheen: f64,
+    emission: Vec3,
+}
+
+impl Principled {
+    /// A rough plastic of color `base_color`.
+    pub fn new(base_color: Vec3) -> Self {
+        Principled {
+            base_color,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            transmission: 0.0,
+            ior: 1.5,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.1,
+            sheen: 0.0,
+            emission: Vec3::ZERO,
+        }
+    }
+
+    pub fn with_metallic(self, metallic: f64) -> Self {
+        Principled { metallic, ..self }
+    }
+
+    pub fn with_base_color(self, base_color: Vec3) -> Self {
+        Principled { base_color, ..self }
+    }
+
+    pub fn with_roughness(self, roughness: f64) -> Self {
+        Principled { roughness, ..self }
+    }
+
+    pub fn with_specular(self, specular: f64) -> Self {
+        Principled { specular, ..self }
+    }
+
+    /// Turns the dielectric base into glass of index of refraction `ior`, tinted by the base
+    /// color.
+    pub fn with_transmission(self, transmission: f64, ior: f64) -> Self {
+        Principled {
+            transmission,
+            ior,
+            ..self
+        }
+    }
+
+    pub fn with_clearcoat(self, clearcoat: f64, clearcoat_roughness: f64) -> Self {
+        Principled {
+            clearcoat,
+            clearcoat_roughness,
+            ..self
+        }
+    }
+
+    /// Grazing retro-reflection of cloth.
+    pub fn with_sheen(self, sheen: f64) -> Self {
+        Principled { sheen, ..self }
+    }
+
+    pub fn with_emission(self, emission: Vec3) -> Self {
+        Principled { emission, ..self }
+    }
+
+    fn lobes(&self, ray: &Ray, normal: &NormVec3) -> Lobes {
+        let wo = -*ray.direction.normalize();
+        let frame = Onb::from_w(if wo.dot(normal) >= 0.0 {
+            *normal
+        } else {
+            -*normal
+        });
+        let wo = frame.project(&wo);
+        let cos_o = wo.z();
+        let ggx = Ggx::new(self.roughness);
+
+        let diffuse = (1.0 - self.metallic) * (1.0 - self.transmission);
+        let glass = (1.0 - self.metallic) * self.transmission;
+        // Schlick's approximation is affine in f0: the dielectric and metallic specular lobes
+        // add up to one.
+        let specular = diffuse + self.metallic;
+        let f0 = if specular > 0.0 {
+            (Vec3::new([0.08 * self.specular; 3]) * diffuse + self.base_color * self.metallic)
+                / specular
+        } else {
+            Vec3::ZERO
+        };
+        let clearcoat = 0.25 * self.clearcoat;
+        let clearcoat_f0 = Vec3::new([0.04; 3]);
+
+        let probabilities = [
+            diffuse * (luminance(self.base_color) + self.sheen),
+            specular * luminance(fresnel_schlick(cos_o, f0)),
+            glass,
+            clearcoat * luminance(fresnel_schlick(cos_o, clearcoat_f0)),
+        ];
+        let total: f64 = probabilities.iter().sum();
+        let probabilities = if total > 0.0 {
+            probabilities.map(|p| p / total)
+        } else {
+            [1.0, 0.0, 0.0, 0.0]
+        };
+
+        let microfacet = |ggx, interface| Microfacet::new(ray, normal, ggx, interface);
+        Lobes {
+            frame,
+            wo,
+            base_color: self.base_color,
+            roughness: self.roughness,
+            sheen: self.sheen,
+            diffuse,
+            specular: (specular, microfacet(ggx, Interface::Schlick { f0 })),
+            glass: (
+                glass,
+                microfacet(ggx, Interface::Dielectric { eta: self.ior }),
+            ),
+            clearcoat: (
+                clearcoat,
+                microfacet(
+                    Ggx::new(self.clearcoat_roughness),
+                    Interface::Schlick { f0: clearcoat_f0 },
+                ),
+            ),
+            probabilities,
+        }
+    }
+}
+
+impl Material for Principled {
+    fn ray(&self, ray: &Ray, location: &Vec3, normal: &Onb, _uv: [f64; 2]) -> RayResult {
+        RayResult {
+            emit: self.emission,
+            ..self.lobes(ray, &normal.w()).scatter(location)
+        }
+    }
+
+    fn bsdf(
+        &self,
+        ray: &Ray,
+        normal: &NormVec3,
+        scattered: &Ray,
+        _albedo: Vec3,
+        _uv: [f64; 2],
+    ) -> Vec3 {
+        self.lobes(ray, normal).eval(&scattered.direction)
+    }
+
+    fn emission(&self) -> Vec3 {
+        self.emission
+    }
+}
+
+/// The lobes of a `Principled` material scattering a ray, with their weights in the BSDF.
+#[derive(Clone, Copy, Debug)]
+pub struct Lobes {
+    /// Its `w` is the normal on the side of the ray's origin.
+    frame: Onb,
+    wo: Vec3,
+    base_color: Vec3,
+    roughness: f64,
+    sheen: f64,
+    diffuse: f64,
+    specular: (f64, Microfacet),
+    glass: (f64, Microfacet),
+    clearcoat: (f64, Microfacet),
+    /// Of sampling the diffuse, specular, glass and clearcoat lobes.
+    probabilities: [f64; 4],
+}
+
+impl Bsdf for Lobes {
+    fn eval(&self, direction: &Vec3) -> Vec3 {
+        let mut f = Vec3::ZERO;
+        let wi = *self.frame.project(direction).normalize();
+        if self.diffuse > 0.0 && wi.z() > 0.0 {
+            let wo = self.wo;
+            let cos_d = wi.dot(&(wo + wi).normalize());
+            let schlick = |cos: f64| (1.0 - cos).clamp(0.0, 1.0).powi(5);
+            // Burley's diffuse darkens smooth surfaces and brightens rough ones at grazing
+            // angles.
+            let fd90 = 0.5 + 2.0 * self.roughness * cos_d.powi(2);
+            let fd = (1.0 + (fd90 - 1.0) * schlick(wi.z()))
+                * (1.0 + (fd90 - 1.0) * schlick(wo.z().max(0.0)));
+            let sheen = Vec3::new([self.sheen * schlick(cos_d); 3]);
+            f = f + (self.base_color * (fd / PI) + sheen) * (self.diffuse * wi.z());
+        }
+        for (weight, microfacet) in [self.specular, self.clearcoat] {
+            if weight > 0.0 {
+                f = f + microfacet.eval(direction) * weight;
+            }
+        }
+        let (weight, glass) = self.glass;
+        if weight > 0.0 {
+            let tint = if wi.z() < 0.0 {
+                self.base_color
+            } else {
+                Vec3::new([1.0; 3])
+            };
+            f = f + glass.eval(direction) * tint * weight;
+        }
+        f
+    }
+}
+
+impl Pdf for Lobes {
+    fn value(&self, direction: &Vec3) -> f64 {
+        let [diffuse, specular, glass, clearcoat] = self.probabilities;
+        let mut value = 0.0;
+        if diffuse > 0.0 {
+            let cos = self.frame.project(&direction.normalize()).z();
+            value += diffuse * cos.max(0.0) / PI;
+        }
+        for (p, (_, microfacet)) in [
+            (specular, self.specular),
+            (glass, self.glass),
+            (clearcoat, self.clearcoat),
+        ] {
+            if p > 0.0 {
+                value += p * microfacet.value(direction);
+            }
+        }
+        value
+    }
+
+    fn generate(&self) -> Vec3 {
+        let [diffuse, specular, glass, _] = self.probabilities;
+        let x = rng::with(|rng| rng.gen::<f64>());
+        if x < diffuse {
+            self.frame
+                .local(rng::with(|rng| *Vec3::random_cosine_direction(rng)))
+        } else if x < diffuse + specular {
+            self.specular.1.generate()
+        } else if x < diffuse + specular + glass {
+            self.glass.1.generate()
+        } else {
+            self.clearcoat.1.generate()
+        }
+    }
+}
+
+#[test]
+fn test() {
+    use super::microfacet::check_bsdf;
+
+    let ray = Ray::new(Vec3::ZERO, Vec3::new([0.6, 0.0, -0.8]));
+    let normal = Vec3::new([0.0, 0.0, 1.0]).normalize();
+    let base_color = Vec3::new([0.8, 0.5, 0.2]);
+    for material in [
+        Principled::new(base_color),
+        Principled::new(base_color)
+            .with_metallic(1.0)
+            .with_roughness(0.3),
+        Principled::new(base_color)
+            .with_transmission(0.8, 1.5)
+            .with_roughness(0.5),
+        Principled::new(base_color)
+            .with_clearcoat(1.0, 0.1)
+            .with_sheen(0.5),
+    ] {
+        check_bsdf(&material.lobes(&ray, &normal), 1.05);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{
+    onb::Onb,
+    ray::Ray,
+    vec3::{NormVec3, Vec3},
+};
+
+use super::{
+    microfacet::{Bsdf, Ggx, Interface, Microfacet},
+    Material, RayResult,
+};
+
+/// Frosted glass: a dielectric whose microfacets blur both reflections and refractions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoughDielectric {
+    ri: f64,
+    ggx: Ggx,
+}
+
+impl RoughDielectric {
+    pub fn new(ri: f64, roughness: f64) -> Self {
+        RoughDielectric {
+            ri,
+            ggx: Ggx::new(roughness),
+        }
+    }
+
+    fn microfacet(&self, ray: &Ray, normal: &NormVec3) -> Microfacet {
+        Microfacet::new(
+            ray,
+            normal,
+            self.ggx,
+            Interface::Dielectric { eta: self.ri },
+        )
+    }
+}
+
+impl Material for RoughDielectric {
+    fn ray(&self, ray: &Ray, location: &Vec3, normal: &Onb, _uv: [f64; 2]) -> RayResult {
+        self.microfacet(ray, &normal.w()).scatter(location)
+    }
+
+    fn bsdf(
+        &self,
+        ray: &Ray,
+        normal: &NormVec3,
+        scattered: &Ray,
+        _albedo: Vec3,
+        _uv: [f64; 2],
+    ) -> Vec3 {
+        self.microfacet(ray, normal).eval(&scattered.direction)
+    }
+}
@@ -1,8 +1,14 @@
 use std::sync::Arc;
 
-use crate::{onb::Onb, pdf::CosinePdf, ray::Ray, rng, vec3::Vec3};
+use crate::{
+    onb::Onb,
+    pdf::CosinePdf,
+    ray::Ray,
+    rng,
+    vec3::{NormVec3, Vec3},
+};
 
-use super::{Material, RayResult};
+use super::{principled::Principled, Material, RayResult};
 
 pub struct Image {
     width: usize,
@@ -19,6 +25,29 @@ impl Image {
         }
     }
 
+    /// Decodes an 8-bit RGB image from a file.
+    #[cfg(feature = "image")]
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, crate::Error> {
+        let path = path.as_ref();
+        let img = image::open(path)
+            .map_err(|e| match e {
+                image::ImageError::IoError(source) => crate::Error::Io {
+                    path: path.to_owned(),
+                    source,
+                },
+                e => crate::Error::Image {
+                    path: path.to_owned(),
+                    message: e.to_string(),
+                },
+            })?
+            .into_rgb8();
+        Ok(Image::new(
+            img.width() as usize,
+            img.height() as usize,
+            img.pixels().map(|p| p.0).collect(),
+        ))
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -38,6 +67,8 @@ impl Image {
 pub struct Tex {
     pixel: Arc<dyn Fn([f32; 2]) -> Vec3 + Send + Sync>,
     poses: [[f32; 2]; 3],
+    /// The material whose base color is the texture. Without one, the texture is diffuse.
+    principled: Option<Principled>,
 }
 
 impl Tex {
@@ -49,22 +80,74 @@ impl Tex {
                 [poses[1][0], 1.0 - poses[1][1]],
                 [poses[2][0], 1.0 - poses[2][1]],
             ],
+            principled: None,
+        }
+    }
+
+    pub fn with_principled(self, principled: Principled) -> Self {
+        Tex {
+            principled: Some(principled),
+            ..self
         }
     }
+
+    fn color(&self, uv: [f64; 2]) -> Vec3 {
+        (self.pixel)(uv_to_xy(self.poses, [uv[0] as f32, uv[1] as f32]))
+    }
+
+    fn principled(&self, uv: [f64; 2]) -> Option<Principled> {
+        let principled = self.principled.clone()?;
+        Some(principled.with_base_color(self.color(uv)))
+    }
 }
 
 impl Material for Tex {
-    fn ray(&self, _ray: &Ray, location: &Vec3, normal: &Onb, uv: [f64; 2]) -> RayResult {
+    fn ray(&self, ray: &Ray, location: &Vec3, normal: &Onb, uv: [f64; 2]) -> RayResult {
+        if let Some(principled) = self.principled(uv) {
+            return principled.ray(ray, location, normal, uv);
+        }
         RayResult {
             emit: Vec3::ZERO,
-            albedo: (self.pixel)(uv_to_xy(self.poses, [uv[0] as f32, uv[1] as f32])),
+            albedo: self.color(uv),
             scattered: Some(Ray::new(
                 *location,
-                *normal.w() + rng::with(|rng| Vec3::random_in_unit_sphere(rng)),
+                Onb::from_w(normal.w()).local(rng::with(|rng| *Vec3::random_cosine_direction(rng))),
             )),
-            pdf: Some(CosinePdf::new(normal.w())),
+            pdf: Some(CosinePdf::new(normal.w()).into()),
+        }
+    }
+
+    fn scattering_pdf(&self, ray: &Ray, normal: &NormVec3, scattered: &Ray, uv: [f64; 2]) -> f64 {
+        if let Some(principled) = self.principled(uv) {
+            return principled.scattering_pdf(ray, normal, scattered, uv);
+        }
+        let cosine = normal.dot(&scattered.direction.normalize());
+        if cosine < 0.0 {
+            0.0
+        } else {
+            cosine / std::f64::consts::PI
         }
     }
+
+    fn bsdf(
+        &self,
+        ray: &Ray,
+        normal: &NormVec3,
+        scattered: &Ray,
+        albedo: Vec3,
+        uv: [f64; 2],
+    ) -> Vec3 {
+        match self.principled(uv) {
+            Some(principled) => principled.bsdf(ray, normal, scattered, albedo, uv),
+            None => albedo * self.scattering_pdf(ray, normal, scattered, uv),
+        }
+    }
+
+    fn emission(&self) -> Vec3 {
+        self.principled
+            .as_ref()
+            .map_or(Vec3::ZERO, |principled| principled.emission())
+    }
 }
 
 pub fn uv_to_xy(poses: [[f32; 2]; 3], uv: [f32; 2]) -> [f32; 2] {
@@ -21,6 +21,11 @@ impl Onb {
         *self.u() * a.x() + *self.v() * a.y() + *self.w() * a.z()
     }
 
+    /// Coordinates of `a` in this basis, the inverse of `local`.
+    pub fn project(&self, a: &Vec3) -> Vec3 {
+        Vec3::new([a.dot(&self.u()), a.dot(&self.v()), a.dot(&self.w())])
+    }
+
     pub fn from_w(w: NormVec3) -> Self {
         let a = if w.x().abs() > 0.9 {
             Vec3::new([0.0, 1.0, 0.0]).normalize()
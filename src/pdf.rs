@@ -1,6 +1,8 @@
 use rand::Rng;
 
 use crate::{
+    lights::Lights,
+    materials::{microfacet::Microfacet, principled::Lobes},
     onb::Onb,
     ray::Ray,
     rng,
@@ -46,6 +48,53 @@ impl Pdf for CosinePdf {
     }
 }
 
+/// The pdfs of the directions scattered by materials.
+pub enum Basic {
+    Cosine(CosinePdf),
+    Microfacet(Microfacet),
+    Principled(Box<Lobes>),
+}
+
+impl Basic {
+    #[inline]
+    #[allow(clippy::should_implement_trait)]
+    pub fn as_ref(&self) -> &dyn Pdf {
+        match self {
+            Basic::Cosine(cosine) => cosine,
+            Basic::Microfacet(microfacet) => microfacet,
+            Basic::Principled(lobes) => &**lobes,
+        }
+    }
+}
+
+impl Pdf for Basic {
+    fn value(&self, direction: &Vec3) -> f64 {
+        self.as_ref().value(direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.as_ref().generate()
+    }
+}
+
+impl From<CosinePdf> for Basic {
+    fn from(pdf: CosinePdf) -> Self {
+        Basic::Cosine(pdf)
+    }
+}
+
+impl From<Microfacet> for Basic {
+    fn from(pdf: Microfacet) -> Self {
+        Basic::Microfacet(pdf)
+    }
+}
+
+impl From<Lobes> for Basic {
+    fn from(pdf: Lobes) -> Self {
+        Basic::Principled(Box::new(pdf))
+    }
+}
+
 pub struct ShapePdf<'a, S: Shape> {
     origin: Vec3,
     shape: &'a S,
@@ -93,6 +142,28 @@ impl<'a, S: Shape> Pdf for ShapesPdf<'a, S> {
     }
 }
 
+/// Directions toward the lights of a scene, selected by power.
+pub struct LightsPdf<'a, S: Shape> {
+    origin: Vec3,
+    lights: &'a Lights<'a, S>,
+}
+
+impl<'a, S: Shape> LightsPdf<'a, S> {
+    pub fn new(origin: Vec3, lights: &'a Lights<'a, S>) -> Self {
+        LightsPdf { origin, lights }
+    }
+}
+
+impl<'a, S: Shape> Pdf for LightsPdf<'a, S> {
+    fn value(&self, direction: &Vec3) -> f64 {
+        self.lights.value(self.origin, direction)
+    }
+
+    fn generate(&self) -> Vec3 {
+        self.lights.generate(self.origin)
+    }
+}
+
 pub struct MixturePdf<'a, P1: Pdf, P2: Pdf> {
     p1: &'a P1,
     p2: &'a P2,
@@ -0,0 +1,225 @@
+//! Phase functions: the angular distribution of the light scattered by a medium
+//!
+//! Densities are over the sphere of scattered directions, and only depend on the angle
+//! to the direction the light was travelling in: a positive anisotropy scatters forward.
+
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::{onb::Onb, pdf::Pdf, rng, vec3::Vec3};
+
+pub trait PhaseFunction {
+    /// Density of scattering at an angle of cosine `cos_theta`.
+    fn value(&self, cos_theta: f64) -> f64;
+    /// Samples the cosine of a scattering angle.
+    fn sample_cos_theta(&self) -> f64;
+}
+
+/// Scatters evenly in all directions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Isotropic;
+
+impl PhaseFunction for Isotropic {
+    fn value(&self, _cos_theta: f64) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn sample_cos_theta(&self) -> f64 {
+        rng::with(|rng| rng.gen_range(-1.0..1.0))
+    }
+}
+
+/// A lobe of mean cosine `g`, in (-1, 1).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HenyeyGreenstein {
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(g: f64) -> Self {
+        HenyeyGreenstein {
+            g: g.clamp(-0.999, 0.999),
+        }
+    }
+}
+
+impl PhaseFunction for HenyeyGreenstein {
+    fn value(&self, cos_theta: f64) -> f64 {
+        let g = self.g;
+        let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    fn sample_cos_theta(&self) -> f64 {
+        let g = self.g;
+        let u = rng::with(|rng| rng.gen::<f64>());
+        if g.abs() < 1e-3 {
+            return 1.0 - 2.0 * u;
+        }
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    }
+}
+
+/// Scattering by particles much smaller than the wavelength, like the molecules of the air.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rayleigh;
+
+impl PhaseFunction for Rayleigh {
+    fn value(&self, cos_theta: f64) -> f64 {
+        3.0 / (16.0 * PI) * (1.0 + cos_theta * cos_theta)
+    }
+
+    fn sample_cos_theta(&self) -> f64 {
+        // Root of the cubic inverting the cumulative distribution, by Cardano's formula.
+        let z = 2.0 * (2.0 * rng::with(|rng| rng.gen::<f64>()) - 1.0);
+        let r = (z * z + 1.0).sqrt();
+        ((z + r).cbrt() + (z - r).cbrt()).clamp(-1.0, 1.0)
+    }
+}
+
+/// A blend of a forward and a backward Henyey-Greenstein lobe, approximating the Mie
+/// scattering of particles about the size of the wavelength, like haze or cloud droplets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DoubleHenyeyGreenstein {
+    forward: HenyeyGreenstein,
+    backward: HenyeyGreenstein,
+    /// Of the forward lobe.
+    weight: f64,
+}
+
+impl DoubleHenyeyGreenstein {
+    pub fn new(forward: f64, backward: f64, weight: f64) -> Self {
+        DoubleHenyeyGreenstein {
+            forward: HenyeyGreenstein::new(forward),
+            backward: HenyeyGreenstein::new(backward),
+            weight: weight.clamp(0.0, 1.0),
+        }
+    }
+
+    /// A strong forward peak with a little back-scattering, for clouds.
+    pub fn mie() -> Self {
+        Self::new(0.8, -0.3, 0.9)
+    }
+}
+
+impl PhaseFunction for DoubleHenyeyGreenstein {
+    fn value(&self, cos_theta: f64) -> f64 {
+        self.weight * self.forward.value(cos_theta)
+            + (1.0 - self.weight) * self.backward.value(cos_theta)
+    }
+
+    fn sample_cos_theta(&self) -> f64 {
+        if rng::with(|rng| rng.gen::<f64>()) < self.weight {
+            self.forward.sample_cos_theta()
+        } else {
+            self.backward.sample_cos_theta()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Basic {
+    Isotropic(Isotropic),
+    HenyeyGreenstein(HenyeyGreenstein),
+    Rayleigh(Rayleigh),
+    DoubleHenyeyGreenstein(DoubleHenyeyGreenstein),
+}
+
+impl Basic {
+    #[inline]
+    #[allow(clippy::should_implement_trait)]
+    pub fn as_ref(&self) -> &dyn PhaseFunction {
+        match self {
+            Basic::Isotropic(isotropic) => isotropic,
+            Basic::HenyeyGreenstein(henyey_greenstein) => henyey_greenstein,
+            Basic::Rayleigh(rayleigh) => rayleigh,
+            Basic::DoubleHenyeyGreenstein(double_henyey_greenstein) => double_henyey_greenstein,
+        }
+    }
+}
+
+impl Default for Basic {
+    fn default() -> Self {
+        Basic::Isotropic(Isotropic)
+    }
+}
+
+impl PhaseFunction for Basic {
+    fn value(&self, cos_theta: f64) -> f64 {
+        self.as_ref().value(cos_theta)
+    }
+
+    fn sample_cos_theta(&self) -> f64 {
+        self.as_ref().sample_cos_theta()
+    }
+}
+
+/// Directions scattered by a phase function from a ray of `direction`.
+pub struct PhasePdf<P: PhaseFunction> {
+    phase: P,
+    frame: Onb,
+}
+
+impl<P: PhaseFunction> PhasePdf<P> {
+    pub fn new(phase: P, direction: &Vec3) -> Self {
+        PhasePdf {
+            phase,
+            frame: Onb::from_w(direction.normalize()),
+        }
+    }
+}
+
+impl<P: PhaseFunction> Pdf for PhasePdf<P> {
+    fn value(&self, direction: &Vec3) -> f64 {
+        self.phase.value(direction.normalize().dot(&self.frame.w()))
+    }
+
+    fn generate(&self) -> Vec3 {
+        let cos_theta = self.phase.sample_cos_theta();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = rng::with(|rng| rng.gen_range(0.0..2.0 * PI));
+        self.frame.local(Vec3::new([
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ]))
+    }
+}
+
+#[test]
+fn test() {
+    let direction = Vec3::new([0.0, 1.0, 1.0]);
+    for phase in [
+        Basic::Isotropic(Isotropic),
+        Basic::HenyeyGreenstein(HenyeyGreenstein::new(0.7)),
+        Basic::HenyeyGreenstein(HenyeyGreenstein::new(-0.4)),
+        Basic::Rayleigh(Rayleigh),
+        Basic::DoubleHenyeyGreenstein(DoubleHenyeyGreenstein::mie()),
+    ] {
+        let pdf = PhasePdf::new(phase, &direction);
+        let cos = |v: &Vec3| v.normalize().dot(&direction.normalize());
+        rng::reseed(0);
+        let n = 200000;
+        // The density integrates to 1, and the moments of the cosine of the sampled
+        // directions match it.
+        let (mut integral, mut moments, mut sampled) = (0.0, [0.0; 2], [0.0; 2]);
+        for _ in 0..n {
+            let v = *rng::with(Vec3::random_unit_vector);
+            let p = pdf.value(&v) * 4.0 * PI;
+            integral += p;
+            moments[0] += cos(&v) * p;
+            moments[1] += cos(&v).powi(2) * p;
+            let v = pdf.generate();
+            sampled[0] += cos(&v);
+            sampled[1] += cos(&v).powi(2);
+        }
+        let n = n as f64;
+        assert!((integral / n - 1.0).abs() < 0.02);
+        for i in 0..2 {
+            assert!((moments[i] / n - sampled[i] / n).abs() < 0.01);
+        }
+    }
+    assert!((HenyeyGreenstein::new(0.5).value(1.0) - 3.0 / (2.0 * PI)).abs() < 1e-9);
+}
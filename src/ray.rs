@@ -4,11 +4,21 @@ use super::vec3::Vec3;
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
+    /// In nanometers, for spectral rendering.
+    pub wavelength: Option<f64>,
 }
 
 impl Ray {
     pub fn new(origin: Vec3, direction: Vec3) -> Ray {
-        Ray { origin, direction }
+        Ray {
+            origin,
+            direction,
+            wavelength: None,
+        }
+    }
+
+    pub fn with_wavelength(self, wavelength: Option<f64>) -> Ray {
+        Ray { wavelength, ..self }
     }
 
     /// Note: The distance is affected by the length of the direction vector.
@@ -0,0 +1,173 @@
+//! Adaptive sampling: stop sampling pixels whose mean has converged
+
+use crate::{tone_mapping::luminance, vec3::Vec3};
+
+use super::tile::{CancelToken, Cancelled};
+
+/// Settings of adaptive sampling.
+///
+/// `sample_per_pixel` becomes the average budget of a tile: every pixel takes `min_samples`,
+/// then the pixels that are still noisy share what the converged ones saved, `min_samples`
+/// at a time, up to `max_samples` each.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Adaptive {
+    pub min_samples: i32,
+    pub max_samples: i32,
+    /// A pixel is converged when the `relative_error` of its luminance falls below this.
+    pub threshold: f64,
+}
+
+impl Adaptive {
+    pub fn new(threshold: f64) -> Self {
+        Adaptive {
+            min_samples: 16,
+            max_samples: 4096,
+            threshold,
+        }
+    }
+
+    pub fn with_samples(self, min_samples: i32, max_samples: i32) -> Self {
+        Adaptive {
+            min_samples,
+            max_samples,
+            ..self
+        }
+    }
+
+    pub fn converged(&self, variance: &Variance) -> bool {
+        variance.count() >= self.min_samples as u32 && variance.relative_error() <= self.threshold
+    }
+
+    /// Samples the pixels of a tile, `take(i, pixel)` taking a sample in pixel `i` and adding
+    /// it to the `variance` of the pixel.
+    pub(super) fn sample<T>(
+        &self,
+        pixels: &mut [T],
+        sample_per_pixel: i32,
+        variance: impl Fn(&T) -> &Variance,
+        mut take: impl FnMut(usize, &mut T),
+        cancel: &CancelToken,
+    ) -> Result<(), Cancelled> {
+        let mut budget = sample_per_pixel as i64 * pixels.len() as i64;
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            for _ in 0..self.min_samples.min(self.max_samples) {
+                take(i, pixel);
+            }
+            budget -= variance(pixel).count() as i64;
+        }
+
+        while budget > 0 {
+            if cancel.is_cancelled() {
+                return Err(Cancelled);
+            }
+            let mut noisy: Vec<_> = (0..pixels.len())
+                .filter(|&i| {
+                    let variance = variance(&pixels[i]);
+                    variance.count() < self.max_samples as u32 && !self.converged(variance)
+                })
+                .collect();
+            if noisy.is_empty() {
+                break;
+            }
+            // The noisiest pixels first, in case the budget runs out.
+            let error = |i: usize| variance(&pixels[i]).relative_error();
+            noisy.sort_by(|&a, &b| error(b).total_cmp(&error(a)));
+            for i in noisy {
+                let remaining = self.max_samples as i64 - variance(&pixels[i]).count() as i64;
+                let n = (self.min_samples.max(1) as i64).min(remaining).min(budget);
+                for _ in 0..n {
+                    take(i, &mut pixels[i]);
+                }
+                budget -= n;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Running mean and variance of the luminance of the samples (Welford's algorithm).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Variance {
+    count: u32,
+    mean: f64,
+    /// Sum of the squared differences to the mean.
+    m2: f64,
+}
+
+impl Variance {
+    pub fn add(&mut self, color: Vec3) {
+        let x = luminance(color);
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Unbiased sample variance.
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        self.m2 / (self.count - 1) as f64
+    }
+
+    /// Standard deviation of the mean.
+    pub fn standard_error(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        (self.variance() / self.count as f64).sqrt()
+    }
+
+    /// Standard error relative to the mean, or to 0.01 for darker means.
+    pub fn relative_error(&self) -> f64 {
+        self.standard_error() / self.mean.max(0.01)
+    }
+}
+
+#[test]
+fn test() {
+    let mut variance = Variance::default();
+    for x in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+        variance.add(Vec3::new([x; 3]));
+    }
+    assert!((variance.mean() - 5.0).abs() < 1e-9);
+    assert!((variance.variance() - 32.0 / 7.0).abs() < 1e-9);
+
+    let adaptive = Adaptive::new(0.1).with_samples(4, 64);
+    assert!(!adaptive.converged(&variance));
+    let mut flat = Variance::default();
+    for _ in 0..4 {
+        flat.add(Vec3::new([0.5; 3]));
+    }
+    assert!(adaptive.converged(&flat));
+
+    // A flat pixel and a very noisy one share a budget of 2 x 20 samples.
+    let mut pixels = [Variance::default(); 2];
+    adaptive
+        .sample(
+            &mut pixels,
+            20,
+            |pixel| pixel,
+            |i, pixel| {
+                let x = match i {
+                    0 => 0.5,
+                    _ if pixel.count() % 2 == 0 => 0.0,
+                    _ => 10.0,
+                };
+                pixel.add(Vec3::new([x; 3]));
+            },
+            &CancelToken::new(),
+        )
+        .unwrap();
+    assert_eq!(pixels[0].count(), 4);
+    assert_eq!(pixels[1].count(), 36);
+}
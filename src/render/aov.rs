@@ -0,0 +1,177 @@
+//! Arbitrary output variables: per-pixel data rendered alongside the image
+
+use std::path::Path;
+
+use crate::{framebuffer::FrameBuffer, vec3::Vec3, Error};
+
+/// What a sample saw on the first surface it hit, besides its color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aov {
+    pub albedo: Vec3,
+    /// Shading normal.
+    pub normal: Vec3,
+    /// Distance from the camera, infinite when nothing was hit.
+    pub depth: f64,
+    pub uv: [f64; 2],
+    pub object: Option<u32>,
+    pub material: Option<u32>,
+    /// Light that scattered at most once before reaching the camera.
+    pub direct: Vec3,
+    /// Light that scattered more than once.
+    pub indirect: Vec3,
+}
+
+impl Default for Aov {
+    fn default() -> Self {
+        Aov {
+            albedo: Vec3::ZERO,
+            normal: Vec3::ZERO,
+            depth: f64::INFINITY,
+            uv: [0.0; 2],
+            object: None,
+            material: None,
+            direct: Vec3::ZERO,
+            indirect: Vec3::ZERO,
+        }
+    }
+}
+
+/// A buffer per output variable.
+///
+/// `albedo`, `normal` and `uv` are the mean of the samples of each pixel, while `depth`,
+/// `object` and `material` come from its first sample since averaging them makes no sense.
+/// `direct` and `indirect` are filtered like the image and add up to it. Scalars are
+/// repeated on the three channels, `uv` is stored in red and green, and missing ids are -1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aovs {
+    pub albedo: FrameBuffer,
+    pub normal: FrameBuffer,
+    pub depth: FrameBuffer,
+    pub uv: FrameBuffer,
+    pub object: FrameBuffer,
+    pub material: FrameBuffer,
+    pub direct: FrameBuffer,
+    pub indirect: FrameBuffer,
+    /// Samples taken in each pixel.
+    pub samples: FrameBuffer,
+}
+
+impl Aovs {
+    pub fn new(width: usize, height: usize) -> Self {
+        let buffer = FrameBuffer::new(width, height);
+        Aovs {
+            albedo: buffer.clone(),
+            normal: buffer.clone(),
+            depth: buffer.clone(),
+            uv: buffer.clone(),
+            object: buffer.clone(),
+            material: buffer.clone(),
+            direct: buffer.clone(),
+            indirect: buffer.clone(),
+            samples: buffer,
+        }
+    }
+
+    /// Writes the per-pixel variables of `aov`, leaving `direct`, `indirect` and `samples`
+    /// alone.
+    pub fn set(&mut self, x: usize, y: usize, aov: &Aov) {
+        let id = |id: Option<u32>| Vec3::new([id.map_or(-1.0, |id| id as f64); 3]);
+        self.albedo.set(x, y, aov.albedo);
+        self.normal.set(x, y, aov.normal);
+        self.depth.set(x, y, Vec3::new([aov.depth; 3]));
+        self.uv.set(x, y, Vec3::new([aov.uv[0], aov.uv[1], 0.0]));
+        self.object.set(x, y, id(aov.object));
+        self.material.set(x, y, id(aov.material));
+    }
+
+    /// The buffers with their names.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &FrameBuffer)> {
+        [
+            ("albedo", &self.albedo),
+            ("normal", &self.normal),
+            ("depth", &self.depth),
+            ("uv", &self.uv),
+            ("object", &self.object),
+            ("material", &self.material),
+            ("direct", &self.direct),
+            ("indirect", &self.indirect),
+            ("samples", &self.samples),
+        ]
+        .into_iter()
+    }
+
+    /// Saves each buffer next to the image saved at `path`, inserting its name before the
+    /// extension: `out.exr` gives `out.albedo.exr`, `out.normal.exr`...
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = path.extension().unwrap_or_default().to_string_lossy();
+        for (name, buffer) in self.iter() {
+            buffer.save(path.with_file_name(format!("{stem}.{name}.{extension}")))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test() {
+    use crate::{
+        camera::Camera,
+        envs::default_env,
+        integrator::PathTracer,
+        lights::Lights,
+        materials::{Basic as Material, Lambertian},
+        render::{CancelToken, TiledRenderer},
+        resolvers::linear_search::LinearSearch,
+        shapes::{Basic as Shape, Sphere},
+    };
+
+    let objects = [
+        (
+            Shape::Sphere(Sphere::new(Vec3::new([0.0, 0.0, -2.0]), 0.5)),
+            Material::Lambertian(Lambertian::new(Vec3::new([0.7, 0.3, 0.3]))),
+        ),
+        (
+            Shape::Sphere(Sphere::new(Vec3::new([0.0, -100.5, -2.0]), 100.0)),
+            Material::Lambertian(Lambertian::new(Vec3::new([0.5; 3]))),
+        ),
+    ];
+    let resolver = LinearSearch::new(objects.iter().map(|(s, m)| (s, m)));
+    let lights = Lights::from_objects(resolver.objects());
+    let path_tracer = PathTracer::new(&lights);
+    let camera = Camera::new(
+        &Vec3::ZERO,
+        &Vec3::new([0.0, 0.0, -1.0]),
+        &Vec3::new([0.0, 1.0, 0.0]),
+        60.0f64.to_radians(),
+        1.0,
+        0.0,
+        1.0,
+    );
+    let (image, aovs) = TiledRenderer::default()
+        .render_aovs(
+            &camera,
+            |ray| path_tracer.sample_aov(&resolver, default_env, ray, |_| 7),
+            21,
+            21,
+            4,
+            &(),
+            &CancelToken::new(),
+        )
+        .unwrap();
+
+    let center = (10, 10);
+    assert_eq!(aovs.object.get(center.0, center.1), Vec3::new([0.0; 3]));
+    assert_eq!(aovs.material.get(center.0, center.1), Vec3::new([7.0; 3]));
+    assert!((aovs.depth.get(center.0, center.1).x() - 1.5).abs() < 0.01);
+    assert!((aovs.normal.get(center.0, center.1).z() - 1.0).abs() < 0.01);
+    assert!((aovs.albedo.get(center.0, center.1) - Vec3::new([0.7, 0.3, 0.3])).norm() < 1e-9);
+    assert_eq!(aovs.object.get(center.0, 20), Vec3::new([1.0; 3]));
+    assert_eq!(aovs.object.get(0, 0), Vec3::new([-1.0; 3]));
+    assert_eq!(aovs.depth.get(0, 0).x(), f64::INFINITY);
+    for (i, &color) in image.pixels().iter().enumerate() {
+        let sum = aovs.direct.pixels()[i] + aovs.indirect.pixels()[i];
+        assert!((sum - color).norm() < 1e-9);
+    }
+    assert!(aovs.indirect.pixels().iter().any(|&c| c != Vec3::ZERO));
+}
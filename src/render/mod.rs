@@ -0,0 +1,51 @@
+pub mod adaptive;
+pub mod aov;
+pub mod progressive;
+pub mod tile;
+
+use rand::Rng;
+
+use crate::{camera::Camera, framebuffer::FrameBuffer, ray::Ray, rng, vec3::Vec3};
+
+pub use adaptive::Adaptive;
+pub use aov::{Aov, Aovs};
+pub use progressive::Progressive;
+pub use tile::{CancelToken, TiledRenderer};
+
+pub fn render(
+    camera: &Camera,
+    sample: impl (Fn(&Ray) -> Vec3) + Send + Sync,
+    width: i32,
+    height: i32,
+    sample_per_pixel: i32,
+) -> FrameBuffer {
+    TiledRenderer::default()
+        .render(
+            camera,
+            sample,
+            width,
+            height,
+            sample_per_pixel,
+            &(),
+            &CancelToken::new(),
+        )
+        .expect("never cancelled")
+}
+
+/// Takes sample `index` of pixel `[x, y]` from the sampler installed with `rng::with_sampler`.
+/// Returns the position of the sample on the image, pixel `[x, y]` being centered on `[x, y]`,
+/// and what `sample` returned.
+fn take_sample<T>(
+    camera: &Camera,
+    sample: &impl Fn(&Ray) -> T,
+    [x, y]: [i32; 2],
+    [width, height]: [i32; 2],
+    seed: u64,
+    index: u32,
+) -> ([f64; 2], T) {
+    rng::start_sample(seed, [x, y], index);
+    let [px, py] = rng::with(|rng| [rng.gen::<f64>(), rng.gen::<f64>()]);
+    let [px, py] = [x as f64 + px - 0.5, y as f64 + py - 0.5];
+    let r = rng::with(|rng| camera.get_ray(px / width as f64, 1.0 - py / height as f64, rng));
+    ([px, py], sample(&r))
+}
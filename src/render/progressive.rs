@@ -0,0 +1,145 @@
+//! Progressive rendering that accumulates passes into a persistent buffer
+
+use std::ops::ControlFlow;
+
+use rayon::prelude::*;
+
+use crate::{camera::Camera, framebuffer::FrameBuffer, ray::Ray, rng, samplers, vec3::Vec3};
+
+use super::take_sample;
+
+/// Sum of all samples taken so far. Render more passes at any time to refine the image.
+pub struct Progressive {
+    width: i32,
+    height: i32,
+    sum: Vec<Vec3>,
+    sample_per_pixel: i32,
+    sampler: samplers::Basic,
+    seed: u64,
+}
+
+impl Progressive {
+    pub fn new(width: i32, height: i32) -> Self {
+        Progressive {
+            width,
+            height,
+            sum: vec![Vec3::ZERO; (width * height) as usize],
+            sample_per_pixel: 0,
+            sampler: samplers::Basic::default(),
+            seed: 0,
+        }
+    }
+
+    /// Passes continue the sequence of the sampler, so low-discrepancy samplers keep their
+    /// stratification across passes.
+    pub fn with_sampler(self, sampler: samplers::Basic) -> Self {
+        Progressive { sampler, ..self }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Progressive { seed, ..self }
+    }
+
+    /// Samples per pixel accumulated so far.
+    pub fn sample_per_pixel(&self) -> i32 {
+        self.sample_per_pixel
+    }
+
+    /// The mean of the accumulated samples.
+    pub fn image(&self) -> FrameBuffer {
+        let n = self.sample_per_pixel.max(1) as f64;
+        FrameBuffer::from_fn(self.width as usize, self.height as usize, |x, y| {
+            self.sum[x + y * self.width as usize] / n
+        })
+    }
+
+    /// Adds `sample_per_pixel` samples to every pixel.
+    pub fn render_pass(
+        &mut self,
+        camera: &Camera,
+        sample: impl (Fn(&Ray) -> Vec3) + Send + Sync,
+        sample_per_pixel: i32,
+    ) {
+        let (width, height) = (self.width, self.height);
+        let first = self.sample_per_pixel;
+        let (sampler, seed) = (&self.sampler, self.seed);
+        self.sum
+            .par_chunks_mut(width as usize)
+            .enumerate()
+            .for_each(|(y, row)| {
+                rng::with_sampler(Box::new(sampler.clone()), || {
+                    for (x, sum) in row.iter_mut().enumerate() {
+                        for i in first..first + sample_per_pixel {
+                            let (_, color) = take_sample(
+                                camera,
+                                &sample,
+                                [x as i32, y as i32],
+                                [width, height],
+                                seed,
+                                i as u32,
+                            );
+                            *sum = *sum + color;
+                        }
+                    }
+                })
+            });
+        self.sample_per_pixel += sample_per_pixel;
+    }
+
+    /// Renders up to `passes` passes of `sample_per_pass` samples per pixel.
+    /// `callback` receives the current image after each pass and can stop rendering by
+    /// returning `ControlFlow::Break`.
+    pub fn run(
+        &mut self,
+        camera: &Camera,
+        sample: impl (Fn(&Ray) -> Vec3) + Send + Sync,
+        sample_per_pass: i32,
+        passes: usize,
+        mut callback: impl FnMut(&FrameBuffer, i32) -> ControlFlow<()>,
+    ) {
+        for _ in 0..passes {
+            self.render_pass(camera, &sample, sample_per_pass);
+            if callback(&self.image(), self.sample_per_pixel).is_break() {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+fn test() {
+    use rand::Rng;
+
+    let camera = Camera::new(
+        &Vec3::ZERO,
+        &Vec3::new([0.0, 0.0, -1.0]),
+        &Vec3::new([0.0, 1.0, 0.0]),
+        1.0,
+        1.0,
+        0.0,
+        1.0,
+    );
+    let sample = |_: &Ray| Vec3::new([1.0, rng::with(|rng| rng.gen::<f64>()), 0.0]);
+
+    // Passes continue the sample indices: two passes take the same samples as one.
+    let mut resumed = Progressive::new(4, 3);
+    let mut passes = 0;
+    resumed.run(&camera, sample, 8, 10, |_, _| {
+        passes += 1;
+        if passes == 2 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    let mut whole = Progressive::new(4, 3);
+    whole.render_pass(&camera, sample, 16);
+    assert_eq!(resumed.sample_per_pixel(), 16);
+    let (resumed, whole) = (resumed.image(), whole.image());
+    for (x, y) in (0..4).flat_map(|x| (0..3).map(move |y| (x, y))) {
+        assert_eq!(resumed.get(x, y), whole.get(x, y));
+        // The mean of the samples.
+        assert_eq!(resumed.get(x, y).r(), 1.0);
+        assert!((resumed.get(x, y).g() - 0.5).abs() < 0.25);
+    }
+}
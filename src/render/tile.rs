@@ -0,0 +1,520 @@
+//! Tile based rendering with progress reporting and cancellation
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    camera::Camera, filter::Filter, framebuffer::FrameBuffer, ray::Ray, rng, samplers, vec3::Vec3,
+};
+
+use super::{
+    adaptive::{Adaptive, Variance},
+    take_sample, Aov, Aovs,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileOrder {
+    /// Left to right, top to bottom.
+    Scanline,
+    /// Outward from the center of the image.
+    Spiral,
+    /// Along a Hilbert curve, keeping consecutive tiles close to each other.
+    Hilbert,
+}
+
+/// A rectangle of pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Splits the image into tiles of at most `tile_size` pixels square. Panics if `tile_size`
+/// isn't positive.
+pub fn tiles(width: i32, height: i32, tile_size: i32, order: TileOrder) -> Vec<Tile> {
+    assert!(
+        tile_size > 0,
+        "tile size must be positive, not {}",
+        tile_size
+    );
+    let nx = (width + tile_size - 1) / tile_size;
+    let ny = (height + tile_size - 1) / tile_size;
+    let mut coords: Vec<_> = (0..ny)
+        .flat_map(|ty| (0..nx).map(move |tx| (tx, ty)))
+        .collect();
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            let (cx, cy) = ((nx - 1) as f64 / 2.0, (ny - 1) as f64 / 2.0);
+            let key = |&(tx, ty): &(i32, i32)| {
+                let (dx, dy) = (tx as f64 - cx, ty as f64 - cy);
+                (dx.abs().max(dy.abs()), dy.atan2(dx))
+            };
+            coords.sort_by(|a, b| {
+                let (a, b) = (key(a), key(b));
+                a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+            });
+        }
+        TileOrder::Hilbert => {
+            let n = (nx.max(ny) as u32).next_power_of_two();
+            coords.sort_by_key(|&(tx, ty)| hilbert_index(n, tx as u32, ty as u32));
+        }
+    }
+    coords
+        .into_iter()
+        .map(|(tx, ty)| Tile {
+            x: tx * tile_size,
+            y: ty * tile_size,
+            width: tile_size.min(width - tx * tile_size),
+            height: tile_size.min(height - ty * tile_size),
+        })
+        .collect()
+}
+
+/// Distance along the Hilbert curve filling an `n` x `n` grid, `n` being a power of two.
+fn hilbert_index(n: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+        d += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - (x & (s - 1));
+                y = s - 1 - (y & (s - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
+
+/// State of a tiled render passed to `Progress`.
+#[derive(Clone, Copy, Debug)]
+pub struct Report {
+    pub done: usize,
+    pub total: usize,
+    pub elapsed: Duration,
+}
+
+impl Report {
+    /// Estimated time remaining, assuming the remaining tiles take as long as the finished ones.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.done == 0 {
+            return None;
+        }
+        Some(
+            self.elapsed
+                .mul_f64((self.total - self.done) as f64 / self.done as f64),
+        )
+    }
+}
+
+pub trait Progress: Sync {
+    /// Called from the worker threads each time a tile is finished.
+    fn tile_done(&self, tile: &Tile, report: Report);
+}
+
+impl Progress for () {
+    fn tile_done(&self, _tile: &Tile, _report: Report) {}
+}
+
+impl<F: Fn(&Tile, Report) + Sync> Progress for F {
+    fn tile_done(&self, tile: &Tile, report: Report) {
+        self(tile, report)
+    }
+}
+
+/// Shared flag to abort a render from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The render was stopped through a `CancelToken`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+pub struct TiledRenderer {
+    pub tile_size: i32,
+    pub order: TileOrder,
+    pub filter: Filter,
+    pub sampler: samplers::Basic,
+    /// Renders with the same seed are identical, whatever the number of threads.
+    pub seed: u64,
+    /// Spends more samples on noisy pixels than on converged ones when set.
+    pub adaptive: Option<Adaptive>,
+}
+
+impl Default for TiledRenderer {
+    fn default() -> Self {
+        TiledRenderer::new(32, TileOrder::Hilbert)
+    }
+}
+
+impl TiledRenderer {
+    /// Tiles are started in `order`. Panics if `tile_size` isn't positive.
+    pub fn new(tile_size: i32, order: TileOrder) -> Self {
+        assert!(
+            tile_size > 0,
+            "tile size must be positive, not {}",
+            tile_size
+        );
+        TiledRenderer {
+            tile_size,
+            order,
+            filter: Filter::default(),
+            sampler: samplers::Basic::default(),
+            seed: 0,
+            adaptive: None,
+        }
+    }
+
+    pub fn with_filter(self, filter: Filter) -> Self {
+        TiledRenderer { filter, ..self }
+    }
+
+    pub fn with_sampler(self, sampler: samplers::Basic) -> Self {
+        TiledRenderer { sampler, ..self }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        TiledRenderer { seed, ..self }
+    }
+
+    pub fn with_adaptive(self, adaptive: Option<Adaptive>) -> Self {
+        TiledRenderer { adaptive, ..self }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(
+        &self,
+        camera: &Camera,
+        sample: impl (Fn(&Ray) -> Vec3) + Send + Sync,
+        width: i32,
+        height: i32,
+        sample_per_pixel: i32,
+        progress: &impl Progress,
+        cancel: &CancelToken,
+    ) -> Result<FrameBuffer, Cancelled> {
+        self.render_aovs(
+            camera,
+            |ray| (sample(ray), Aov::default()),
+            width,
+            height,
+            sample_per_pixel,
+            progress,
+            cancel,
+        )
+        .map(|(image, _)| image)
+    }
+
+    /// Renders the image along with the output variables returned by `sample`, e.g. with
+    /// `PathTracer::sample_aov`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_aovs(
+        &self,
+        camera: &Camera,
+        sample: impl (Fn(&Ray) -> (Vec3, Aov)) + Send + Sync,
+        width: i32,
+        height: i32,
+        sample_per_pixel: i32,
+        progress: &impl Progress,
+        cancel: &CancelToken,
+    ) -> Result<(FrameBuffer, Aovs), Cancelled> {
+        let tiles = tiles(width, height, self.tile_size, self.order);
+        let start = Instant::now();
+        let done = AtomicUsize::new(0);
+
+        // Samples near the border of a tile also land on the neighbouring tiles' pixels.
+        let pad = self.filter.radius().ceil() as i32;
+
+        let take = |pixel: &mut Pixel, splats: &mut Splats, [x, y]: [i32; 2]| {
+            let (position, (color, aov)) = take_sample(
+                camera,
+                &sample,
+                [x, y],
+                [width, height],
+                self.seed,
+                pixel.variance.count(),
+            );
+            pixel.add(color, &aov);
+            let colors = [color, aov.direct, aov.indirect];
+            self.filter
+                .splat(position, |x, y, w| splats.add(x, y, colors, w));
+        };
+
+        // Whichever job rayon runs, it renders the next tile, so that tiles start in `order`
+        // rather than in the order of work stealing.
+        let next = AtomicUsize::new(0);
+        let mut results = (0..tiles.len())
+            .into_par_iter()
+            .map(|_| {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let tile = &tiles[index];
+                let mut splats = Splats::new(*tile, pad);
+                let mut pixels = vec![Pixel::new(); (tile.width * tile.height) as usize];
+                let position = |i: usize| {
+                    [
+                        tile.x + i as i32 % tile.width,
+                        tile.y + i as i32 / tile.width,
+                    ]
+                };
+                rng::with_sampler(Box::new(self.sampler.clone()), || {
+                    match self.adaptive {
+                        None => {
+                            for (i, pixel) in pixels.iter_mut().enumerate() {
+                                if i % tile.width as usize == 0 && cancel.is_cancelled() {
+                                    return Err(Cancelled);
+                                }
+                                for _ in 0..sample_per_pixel {
+                                    take(pixel, &mut splats, position(i));
+                                }
+                            }
+                        }
+                        Some(adaptive) => adaptive.sample(
+                            &mut pixels,
+                            sample_per_pixel,
+                            |pixel| &pixel.variance,
+                            |i, pixel| take(pixel, &mut splats, position(i)),
+                            cancel,
+                        )?,
+                    }
+                    Ok(())
+                })?;
+                let report = Report {
+                    done: done.fetch_add(1, Ordering::Relaxed) + 1,
+                    total: tiles.len(),
+                    elapsed: start.elapsed(),
+                };
+                progress.tile_done(tile, report);
+                Ok((index, splats, pixels))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        results.sort_by_key(|&(index, ..)| index);
+
+        let mut sum = Splats::new(
+            Tile {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            },
+            0,
+        );
+        let mut aovs = Aovs::new(width as usize, height as usize);
+        for (tile, (_, splats, pixels)) in tiles.iter().zip(&results) {
+            sum.merge(splats);
+            for (i, pixel) in pixels.iter().enumerate() {
+                let x = tile.x as usize + i % tile.width as usize;
+                let y = tile.y as usize + i / tile.width as usize;
+                aovs.set(x, y, &pixel.aov());
+                let count = pixel.variance.count() as f64;
+                aovs.samples.set(x, y, Vec3::new([count; 3]));
+            }
+        }
+        let resolve = |c: usize| {
+            FrameBuffer::from_fn(width as usize, height as usize, |x, y| {
+                let i = x + y * width as usize;
+                if sum.weight[i] > 0.0 {
+                    sum.colors[i][c] / sum.weight[i]
+                } else {
+                    Vec3::ZERO
+                }
+            })
+        };
+        aovs.direct = resolve(1);
+        aovs.indirect = resolve(2);
+        Ok((resolve(0), aovs))
+    }
+}
+
+/// Samples taken in a pixel.
+#[derive(Clone, Copy)]
+struct Pixel {
+    /// Output variables of the first sample.
+    first: Aov,
+    /// Sums of the albedo, normal and uv of the samples.
+    sums: [Vec3; 3],
+    variance: Variance,
+}
+
+impl Pixel {
+    fn new() -> Self {
+        Pixel {
+            first: Aov::default(),
+            sums: [Vec3::ZERO; 3],
+            variance: Variance::default(),
+        }
+    }
+
+    fn add(&mut self, color: Vec3, aov: &Aov) {
+        if self.variance.count() == 0 {
+            self.first = *aov;
+        }
+        self.sums[0] = self.sums[0] + aov.albedo;
+        self.sums[1] = self.sums[1] + aov.normal;
+        self.sums[2] = self.sums[2] + Vec3::new([aov.uv[0], aov.uv[1], 0.0]);
+        self.variance.add(color);
+    }
+
+    /// Output variables of the pixel, as described by `Aovs`.
+    fn aov(&self) -> Aov {
+        let n = self.variance.count().max(1) as f64;
+        Aov {
+            albedo: self.sums[0] / n,
+            normal: self.sums[1] / n,
+            uv: [self.sums[2].x() / n, self.sums[2].y() / n],
+            ..self.first
+        }
+    }
+}
+
+/// Weighted sum of the samples falling on a tile and its `pad` pixels wide border.
+struct Splats {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    /// Color, direct and indirect light.
+    colors: Vec<[Vec3; 3]>,
+    weight: Vec<f64>,
+}
+
+impl Splats {
+    fn new(tile: Tile, pad: i32) -> Self {
+        let (width, height) = (tile.width + 2 * pad, tile.height + 2 * pad);
+        Splats {
+            x: tile.x - pad,
+            y: tile.y - pad,
+            width,
+            height,
+            colors: vec![[Vec3::ZERO; 3]; (width * height) as usize],
+            weight: vec![0.0; (width * height) as usize],
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let (x, y) = (x - self.x, y - self.y);
+        if 0 <= x && x < self.width && 0 <= y && y < self.height {
+            Some((x + y * self.width) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn add(&mut self, x: i32, y: i32, colors: [Vec3; 3], weight: f64) {
+        if let Some(i) = self.index(x, y) {
+            for (sum, color) in self.colors[i].iter_mut().zip(colors) {
+                *sum = *sum + color * weight;
+            }
+            self.weight[i] += weight;
+        }
+    }
+
+    fn merge(&mut self, other: &Splats) {
+        for y in other.y..other.y + other.height {
+            for x in other.x..other.x + other.width {
+                if let (Some(i), Some(j)) = (self.index(x, y), other.index(x, y)) {
+                    for (sum, color) in self.colors[i].iter_mut().zip(other.colors[j]) {
+                        *sum = *sum + color;
+                    }
+                    self.weight[i] += other.weight[j];
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test() {
+    for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert] {
+        let tiles = tiles(100, 70, 32, order);
+        assert_eq!(tiles.len(), 12);
+        assert_eq!(
+            tiles.iter().map(|t| t.width * t.height).sum::<i32>(),
+            100 * 70
+        );
+    }
+    let hilbert = tiles(128, 128, 32, TileOrder::Hilbert);
+    for w in hilbert.windows(2) {
+        assert_eq!((w[0].x - w[1].x).abs() + (w[0].y - w[1].y).abs(), 32);
+    }
+}
+
+#[test]
+fn test_deterministic() {
+    use crate::{
+        materials::{Basic as Material, Lambertian},
+        resolvers::linear_search::LinearSearch,
+        shapes::{Basic as Shape, Sphere},
+    };
+
+    let objects = [
+        (
+            Shape::Sphere(Sphere::new(Vec3::new([0.0, 0.0, -1.0]), 0.5)),
+            Material::Lambertian(Lambertian::new(Vec3::new([0.7, 0.3, 0.3]))),
+        ),
+        (
+            Shape::Sphere(Sphere::new(Vec3::new([0.0, -100.5, -1.0]), 100.0)),
+            Material::Lambertian(Lambertian::new(Vec3::new([0.5, 0.5, 0.5]))),
+        ),
+    ];
+    let resolver = LinearSearch::new(objects.iter().map(|(s, m)| (s, m)));
+    let camera = Camera::new(
+        &Vec3::ZERO,
+        &Vec3::new([0.0, 0.0, -1.0]),
+        &Vec3::new([0.0, 1.0, 0.0]),
+        90.0f64.to_radians(),
+        1.5,
+        0.0,
+        1.0,
+    );
+    let render = |threads: usize, seed: u64| {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            TiledRenderer::new(8, TileOrder::Hilbert)
+                .with_filter(Filter::Tent { radius: 1.0 })
+                .with_seed(seed)
+                .render(
+                    &camera,
+                    |ray| crate::sample::sample(&resolver, crate::envs::default_env, ray, 10),
+                    30,
+                    20,
+                    4,
+                    &(),
+                    &CancelToken::new(),
+                )
+                .unwrap()
+        })
+    };
+    let image = render(1, 0);
+    assert_eq!(render(4, 0), image);
+    assert_ne!(render(4, 1), image);
+}
@@ -19,11 +19,13 @@ pub enum BVH<M: Clone, O: Hit<M>> {
 }
 
 impl<S: Shape, DS: std::ops::Deref<Target = S> + Clone, M: Clone> BVH<M, Object<DS, M>> {
+    /// Objects are numbered in the order of `it`.
     pub fn new(it: impl Iterator<Item = (DS, M)>) -> Self {
         let mut objs: Vec<_> = it
-            .map(|(s, m)| {
+            .enumerate()
+            .map(|(i, (s, m))| {
                 let bbox = s.bbox();
-                Object::new(s, m, bbox)
+                Object::new(s, m, bbox).with_id(i as u32)
             })
             .collect();
         let a = Self::build(&mut objs);
@@ -91,6 +93,22 @@ impl<M: Clone, O: Hit<M> + AsRef<BBox> + Clone> BVH<M, O> {
     }
 }
 
+impl<M: Clone, O: Hit<M>> BVH<M, O> {
+    /// The objects in the leaves, from left to right.
+    pub fn objects(&self) -> impl Iterator<Item = &O> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || loop {
+            match stack.pop()? {
+                BVH::Object { object, .. } => return Some(object),
+                BVH::Pair { left, right, .. } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        })
+    }
+}
+
 impl<M: Clone, O: Hit<M> + AsRef<BBox>> BVH<M, O> {
     fn hit_(&self, ray: &Ray, tmax: f64, res: &mut Option<(HitRec, M)>) {
         let tmin = 1e-6;
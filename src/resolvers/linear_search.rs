@@ -12,11 +12,13 @@ pub struct LinearSearch<M: Clone, O: Hit<M>> {
 }
 
 impl<S: Shape, DS: std::ops::Deref<Target = S> + Clone, M: Clone> LinearSearch<M, Object<DS, M>> {
+    /// Objects are numbered in the order of `it`.
     pub fn new(it: impl Iterator<Item = (DS, M)>) -> Self {
         let objects: Vec<_> = it
-            .map(|(s, m)| {
+            .enumerate()
+            .map(|(i, (s, m))| {
                 let bbox = s.bbox();
-                Object::new(s, m, bbox)
+                Object::new(s, m, bbox).with_id(i as u32)
             })
             .collect();
         let bbox = BBox::from_bboxes(objects.iter()).unwrap();
@@ -40,6 +42,12 @@ impl<M: Clone, O: Hit<M> + AsRef<BBox>> LinearSearch<M, O> {
     }
 }
 
+impl<M: Clone, O: Hit<M>> LinearSearch<M, O> {
+    pub fn objects(&self) -> impl Iterator<Item = &O> {
+        self.objects.iter()
+    }
+}
+
 impl<M: Clone, O: Hit<M> + AsRef<BBox>> Hit<M> for LinearSearch<M, O> {
     #[inline]
     fn hit_with_range(
@@ -9,6 +9,7 @@ pub struct Object<S, M: Clone> {
     shape: S,
     material: M,
     bbox: BBox,
+    id: u32,
 }
 
 impl<S, M: Clone> Object<S, M> {
@@ -17,16 +18,40 @@ impl<S, M: Clone> Object<S, M> {
             shape,
             material,
             bbox,
+            id: 0,
         }
     }
+
+    /// Sets the `HitRec::object` of the hits on this object.
+    pub fn with_id(self, id: u32) -> Self {
+        Object { id, ..self }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn shape(&self) -> &S {
+        &self.shape
+    }
+
+    pub fn material(&self) -> &M {
+        &self.material
+    }
 }
 
 impl<S: Shape, DS: std::ops::Deref<Target = S> + Clone, M: Clone> Hit<M> for Object<DS, M> {
     #[inline]
     fn hit_with_range(&self, ray: &Ray, tmin: f64, tmax: f64) -> Option<(HitRec, M)> {
-        self.shape
-            .hit(ray, tmin, tmax)
-            .map(|hr| (hr, self.material.clone()))
+        self.shape.hit(ray, tmin, tmax).map(|hr| {
+            (
+                HitRec {
+                    object: self.id,
+                    ..hr
+                },
+                self.material.clone(),
+            )
+        })
     }
 }
 
@@ -43,6 +68,7 @@ impl<S: Clone, M: Clone> Clone for Object<S, M> {
             shape: self.shape.clone(),
             material: self.material.clone(),
             bbox: self.bbox.clone(),
+            id: self.id,
         }
     }
 }
@@ -43,6 +43,7 @@ impl<M: Clone, T: Hit<M>> Hit<M> for Transformed<M, T> {
                     ),
                     uv: hr.uv,
                     front: hr.front,
+                    object: hr.object,
                 },
                 m,
             )
@@ -1,24 +1,118 @@
 use std::cell::UnsafeCell;
 
-use rand::SeedableRng;
+use rand::{RngCore, SeedableRng};
 use rand_pcg::Lcg128Xsl64;
 
+use crate::samplers::Sampler;
+
 pub type MainRng = Lcg128Xsl64;
 
+/// The generator behind `with`. Draws from the installed sampler if any, from a PCG
+/// otherwise.
+pub struct ThreadRng {
+    rng: MainRng,
+    sampler: Option<Box<dyn Sampler>>,
+    /// Second half of the last `Sampler::next_2d`, so that draws are stratified by pairs.
+    pending: Option<f64>,
+    bounce: u32,
+}
+
+impl ThreadRng {
+    fn next_f64(&mut self) -> Option<f64> {
+        let sampler = self.sampler.as_mut()?;
+        Some(self.pending.take().unwrap_or_else(|| {
+            let [a, b] = sampler.next_2d();
+            self.pending = Some(b);
+            a
+        }))
+    }
+}
+
+impl RngCore for ThreadRng {
+    fn next_u32(&mut self) -> u32 {
+        match self.next_f64() {
+            Some(x) => (x * (1u64 << 32) as f64) as u32,
+            None => self.rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self.next_f64() {
+            Some(x) => (x * 2.0f64.powi(64)) as u64,
+            None => self.rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
 thread_local!(
-    pub static THREAD_RNG_KEY: UnsafeCell<MainRng> = {
+    pub static THREAD_RNG_KEY: UnsafeCell<ThreadRng> = {
         let rng = SeedableRng::seed_from_u64(0);
-        UnsafeCell::new(rng)
+        UnsafeCell::new(ThreadRng {
+            rng,
+            sampler: None,
+            pending: None,
+            bounce: 0,
+        })
     }
 );
 
 #[inline]
-pub fn with<F: FnOnce(&mut MainRng) -> R, R>(f: F) -> R {
+pub fn with<F: FnOnce(&mut ThreadRng) -> R, R>(f: F) -> R {
     THREAD_RNG_KEY.with(|rng| f(unsafe { &mut *rng.get() }))
 }
 
+/// Reseeds the PCG. Has no effect on draws while a sampler is installed.
 pub fn reseed(seed: u64) {
-    THREAD_RNG_KEY.with(|rng| *unsafe { &mut *rng.get() } = SeedableRng::seed_from_u64(seed));
+    with(|rng| rng.rng = SeedableRng::seed_from_u64(seed));
+}
+
+/// Makes `with` draw from `sampler` on this thread during `f`.
+pub fn with_sampler<R>(sampler: Box<dyn Sampler>, f: impl FnOnce() -> R) -> R {
+    let previous = with(|rng| {
+        rng.pending = None;
+        rng.sampler.replace(sampler)
+    });
+    let r = f();
+    with(|rng| {
+        rng.pending = None;
+        rng.sampler = previous;
+    });
+    r
+}
+
+/// Starts sample `index` of `pixel` on the installed sampler.
+pub fn start_sample(seed: u64, pixel: [i32; 2], index: u32) {
+    with(|rng| {
+        rng.pending = None;
+        rng.bounce = 0;
+        if let Some(sampler) = &mut rng.sampler {
+            sampler.start(seed, pixel, index);
+        }
+    });
+}
+
+/// Moves the installed sampler to the dimensions of the next bounce. Called by the
+/// integrators on every path vertex.
+pub fn next_bounce() {
+    with(|rng| {
+        rng.pending = None;
+        if let Some(sampler) = &mut rng.sampler {
+            sampler.start_bounce(rng.bounce);
+        }
+        rng.bounce += 1;
+    });
 }
 
 #[test]
@@ -31,4 +125,15 @@ fn test() {
     reseed(0);
     assert_eq!(with(|rng| rng.gen::<usize>()), a);
     assert_eq!(with(|rng| rng.gen::<usize>()), b);
+
+    let mut sampler = crate::samplers::Sobol::new();
+    sampler.start(0, [1, 2], 3);
+    let expected = sampler.next_2d();
+    let drawn = with_sampler(Box::new(sampler), || {
+        start_sample(0, [1, 2], 3);
+        with(|rng| [rng.gen::<f64>(), rng.gen::<f64>()])
+    });
+    assert!((drawn[0] - expected[0]).abs() < 1e-9 && (drawn[1] - expected[1]).abs() < 1e-9);
+    reseed(0);
+    assert_eq!(with(|rng| rng.gen::<usize>()), a);
 }
@@ -1,43 +1,60 @@
 //! Pixel sampling
+//!
+//! Each function follows a path for at most `cutoff` bounces, iteratively, accumulating the
+//! light reaching the camera weighted by the throughput of the path so far.
 
 use std::ops::Deref;
 
 use rand::Rng;
 
+use crate::integrator::PathTracer;
+use crate::lights::Lights;
 use crate::materials::Material;
+use crate::pdf::Pdf;
+use crate::phase::{self, PhasePdf};
 use crate::ray::Ray;
 use crate::resolvers::Hit;
 use crate::rng;
-use crate::shapes::HitRec;
+use crate::shapes::{HitRec, Shape};
 use crate::vec3::Vec3;
 
+pub use crate::integrator::power_heuristic;
+
 pub fn sample<M: Material, DM: Deref<Target = M>>(
     hit: impl Hit<DM>,
     env: impl Fn(&Ray) -> Vec3,
     ray: &Ray,
     cutoff: i32,
 ) -> Vec3 {
-    if cutoff == 0 {
-        return env(ray);
-    }
-
-    if let Some((hit_rec, material)) = hit.hit(ray) {
-        let HitRec {
-            location,
-            normal,
-            uv,
-            ..
-        } = hit_rec;
+    let mut radiance = Vec3::ZERO;
+    let mut throughput = Vec3::new([1.0; 3]);
+    let mut ray = *ray;
+
+    for _ in 0..cutoff {
+        rng::next_bounce();
+
+        let Some((
+            HitRec {
+                location,
+                normal,
+                uv,
+                ..
+            },
+            material,
+        )) = hit.hit(&ray)
+        else {
+            return radiance + throughput * env(&ray);
+        };
 
         let r = material.ray(&ray, &location, &normal, uv);
-        if let Some(scattered) = &r.scattered {
-            r.emit + r.albedo * sample(hit, env, scattered, cutoff - 1)
-        } else {
-            r.emit
-        }
-    } else {
-        env(ray)
+        radiance = radiance + throughput * r.emit;
+        let Some(scattered) = r.scattered else {
+            break;
+        };
+        throughput = throughput * r.albedo;
+        ray = scattered;
     }
+    radiance
 }
 
 /// Importance sampling
@@ -46,134 +63,187 @@ pub fn sample_weighted<M: Material, DM: Deref<Target = M>>(
     env: impl Fn(&Ray) -> Vec3,
     ray: &Ray,
     cutoff: i32,
-    pdf_gen: &impl Fn(crate::pdf::CosinePdf, Vec3) -> (Vec3, f64),
+    pdf_gen: &impl Fn(crate::pdf::Basic, Vec3) -> (Vec3, f64),
 ) -> Vec3 {
-    if cutoff == 0 {
-        return env(ray);
-    }
-
-    if let Some((hit_rec, material)) = hit.hit(ray) {
-        let HitRec {
-            location,
-            normal,
-            uv,
-            ..
-        } = hit_rec;
+    let mut radiance = Vec3::ZERO;
+    let mut throughput = Vec3::new([1.0; 3]);
+    let mut ray = *ray;
+
+    for _ in 0..cutoff {
+        rng::next_bounce();
+
+        let Some((
+            HitRec {
+                location,
+                normal,
+                uv,
+                ..
+            },
+            material,
+        )) = hit.hit(&ray)
+        else {
+            return radiance + throughput * env(&ray);
+        };
 
         let r = material.ray(&ray, &location, &normal, uv);
-        if let Some(scattered) = &r.scattered {
-            let Some(p1) = r.pdf else {
-                return r.albedo * sample_weighted(hit, env, scattered, cutoff - 1, pdf_gen);
-            };
-
-            let (direction, pdf_value) = pdf_gen(p1, location);
-
-            let scattered = Ray::new(location, direction);
-            let scattering_pdf = material.scattering_pdf(&ray, &normal.w(), &scattered);
-            if pdf_value <= 0.0 {
-                return r.emit;
-            }
-            let pdf = scattering_pdf / pdf_value;
-            r.emit + r.albedo * sample_weighted(hit, env, &scattered, cutoff - 1, pdf_gen) * pdf
-        } else {
-            r.emit
+        radiance = radiance + throughput * r.emit;
+        let Some(scattered) = r.scattered else {
+            break;
+        };
+        let Some(p1) = r.pdf else {
+            throughput = throughput * r.albedo;
+            ray = scattered;
+            continue;
+        };
+
+        let (direction, pdf_value) = pdf_gen(p1, location);
+        if pdf_value <= 0.0 {
+            break;
         }
-    } else {
-        env(ray)
+        let scattered = Ray::new(location, direction);
+        let bsdf = material.bsdf(&ray, &normal.w(), &scattered, r.albedo, uv);
+        throughput = throughput * bsdf / pdf_value;
+        ray = scattered;
     }
+    radiance
+}
+
+/// Path tracing with next-event estimation: vertices with a `pdf` also sample a direction
+/// toward `lights`, and both strategies are weighted with the power heuristic. A
+/// `PathTracer` cut after `cutoff` bounces, without Russian roulette.
+pub fn sample_nee<M: Material, DM: Deref<Target = M>, S: Shape>(
+    hit: impl Hit<DM>,
+    lights: &Lights<S>,
+    env: impl Fn(&Ray) -> Vec3,
+    ray: &Ray,
+    cutoff: i32,
+) -> Vec3 {
+    let cutoff = cutoff.max(0) as u32;
+    PathTracer::new(lights)
+        .with_depth(cutoff, cutoff)
+        .sample(hit, env, ray)
 }
 
+/// `volume` is the medium the ray starts in, as (distance to the next scattering, negative
+/// inverse density, color, phase function).
 pub fn sample_with_volume<M: Material, DM: Deref<Target = M>, H: Hit<DM>, E: Fn(&Ray) -> Vec3>(
     hit: H,
     env: E,
     ray: &Ray,
     cutoff: i32,
-    volume: Option<(f64, f64, Vec3)>,
+    volume: Option<(f64, f64, Vec3, phase::Basic)>,
 ) -> Vec3 {
-    if cutoff == 0 {
-        return env(ray);
-    }
-
-    if let Some((
-        HitRec {
-            location,
-            normal,
-            uv,
-            front,
-            time,
-            ..
-        },
-        material,
-    )) = hit.hit(ray)
-    {
-        if let Some(volume) = volume {
-            let scatter_distance = volume.0;
-            if scatter_distance < time * ray.direction.norm() {
-                return subsurface_scattering(volume, ray, hit, env, cutoff);
+    let mut radiance = Vec3::ZERO;
+    let mut throughput = Vec3::new([1.0; 3]);
+    let mut ray = *ray;
+    let mut volume = volume;
+    let mut cutoff = cutoff;
+
+    while cutoff > 0 {
+        rng::next_bounce();
+
+        let hit_rec = hit.hit(&ray);
+        let distance = hit_rec
+            .as_ref()
+            .map_or(f64::INFINITY, |(rec, _)| rec.time * ray.direction.norm());
+        if let Some((scatter_distance, neg_inv_density, color, phase)) = volume {
+            if scatter_distance < distance {
+                (ray, volume) =
+                    subsurface_scattering(&ray, scatter_distance, neg_inv_density, color, phase);
+                throughput = throughput * color;
+                cutoff -= 1;
+                continue;
             }
         }
-        if let Some((neg_inv_density, color)) = material.volume() {
-            if front {
+
+        let Some((
+            HitRec {
+                location,
+                normal,
+                uv,
+                front,
+                ..
+            },
+            material,
+        )) = hit_rec
+        else {
+            return radiance + throughput * env(&ray);
+        };
+
+        if let Some((neg_inv_density, color, phase)) = material.volume() {
+            volume = if front {
                 // into the volume face
-                let ray = Ray::new(location, ray.direction);
-                sample_with_volume(
-                    hit,
-                    env,
-                    &ray,
-                    cutoff,
-                    Some((
-                        make_scatter_distance(neg_inv_density),
-                        neg_inv_density,
-                        color,
-                    )),
-                )
+                Some((
+                    make_scatter_distance(neg_inv_density),
+                    neg_inv_density,
+                    color,
+                    phase,
+                ))
             } else {
                 // out of the volume face
-                sample_with_volume(hit, env, &Ray::new(location, ray.direction), cutoff, None)
-            }
-        } else {
-            let r = material.ray(&ray, &location, &normal, uv);
-            if let Some(scattered) = &r.scattered {
-                let volume = volume.map(|(d, n, c)| (d - time * ray.direction.norm(), n, c));
-                r.emit + r.albedo * sample_with_volume(hit, env, scattered, cutoff - 1, volume)
-            } else {
-                r.emit
-            }
-        }
-    } else {
-        if let Some(volume) = volume {
-            return subsurface_scattering(volume, ray, hit, env, cutoff);
+                None
+            };
+            ray = Ray::new(location, ray.direction);
+            continue;
         }
 
-        env(ray)
+        let r = material.ray(&ray, &location, &normal, uv);
+        radiance = radiance + throughput * r.emit;
+        let Some(scattered) = r.scattered else {
+            break;
+        };
+        volume = volume.map(|(d, n, c, p)| (d - distance, n, c, p));
+        throughput = throughput * r.albedo;
+        ray = scattered;
+        cutoff -= 1;
     }
+    radiance
 }
 
-fn subsurface_scattering<M: Material, DM: Deref<Target = M>, H: Hit<DM>, E: Fn(&Ray) -> Vec3>(
-    volume: (f64, f64, Vec3),
+/// Scatters `ray` at `scatter_distance` in a direction sampled from `phase`, returning the
+/// new ray and the volume it travels in.
+fn subsurface_scattering(
     ray: &Ray,
-    hit: H,
-    env: E,
-    cutoff: i32,
-) -> Vec3 {
-    let (scatter_distance, neg_inv_density, color) = volume;
+    scatter_distance: f64,
+    neg_inv_density: f64,
+    color: Vec3,
+    phase: phase::Basic,
+) -> (Ray, Option<(f64, f64, Vec3, phase::Basic)>) {
     let ray = Ray::new(
         ray.origin + *ray.direction.normalize() * scatter_distance,
-        rng::with(|rng| *Vec3::random_unit_vector(rng)),
+        PhasePdf::new(phase, &ray.direction).generate(),
     );
-    sample_with_volume(
-        hit,
-        env,
-        &ray,
-        cutoff - 1,
+    (
+        ray,
         Some((
             make_scatter_distance(neg_inv_density),
             neg_inv_density,
             color,
+            phase,
         )),
-    ) * color
+    )
 }
 
 pub fn make_scatter_distance(neg_inv_density: f64) -> f64 {
     -neg_inv_density * rng::with(|rng| rng.gen::<f64>()).ln()
 }
+
+#[test]
+fn test() {
+    use crate::phase::HenyeyGreenstein;
+
+    // Scatterings in a volume follow its phase function, whose mean cosine is `g`.
+    let ray = Ray::new(Vec3::ZERO, Vec3::new([2.0, 0.0, 0.0]));
+    let phase = phase::Basic::HenyeyGreenstein(HenyeyGreenstein::new(0.8));
+    rng::reseed(0);
+    let n = 10000;
+    let cos = (0..n)
+        .map(|_| {
+            let (scattered, _) = subsurface_scattering(&ray, 1.0, 1.0, Vec3::new([1.0; 3]), phase);
+            assert_eq!(scattered.origin, Vec3::new([1.0, 0.0, 0.0]));
+            scattered.direction.normalize().x()
+        })
+        .sum::<f64>()
+        / n as f64;
+    assert!((cos - 0.8).abs() < 0.02);
+}
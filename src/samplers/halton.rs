@@ -0,0 +1,59 @@
+use super::{mix_bits, to_unit, Sampler, State};
+
+const PRIMES: [u32; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// The Halton sequence, one prime base per dimension, shifted by a random offset per pixel
+/// and dimension. Dimensions beyond the 32nd are uniform random.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Halton {
+    state: State,
+}
+
+impl Halton {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Sampler for Halton {
+    fn start(&mut self, seed: u64, pixel: [i32; 2], index: u32) {
+        self.state.start(seed, pixel, index);
+    }
+
+    fn start_bounce(&mut self, bounce: u32) {
+        self.state.start_bounce(bounce);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let dimension = self.state.dimension as usize;
+        let hash = self.state.take(1);
+        match PRIMES.get(dimension) {
+            Some(&base) => {
+                let x = radical_inverse(base, self.state.index) + to_unit(hash);
+                if x >= 1.0 {
+                    x - 1.0
+                } else {
+                    x
+                }
+            }
+            None => to_unit(mix_bits(hash ^ self.state.index as u64)),
+        }
+    }
+}
+
+/// Mirrors the digits of `i` in `base` around the radix point.
+fn radical_inverse(base: u32, mut i: u32) -> f64 {
+    let inv_base = 1.0 / base as f64;
+    let mut reversed = 0u64;
+    let mut inv_base_n = 1.0;
+    while i > 0 {
+        let next = i / base;
+        reversed = reversed * base as u64 + (i - next * base) as u64;
+        inv_base_n *= inv_base;
+        i = next;
+    }
+    (reversed as f64 * inv_base_n).min(1.0 - f64::EPSILON / 2.0)
+}
@@ -0,0 +1,28 @@
+use super::{mix_bits, to_unit, Sampler, State};
+
+/// Uniform random numbers, with no stratification.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Independent {
+    state: State,
+}
+
+impl Independent {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Sampler for Independent {
+    fn start(&mut self, seed: u64, pixel: [i32; 2], index: u32) {
+        self.state.start(seed, pixel, index);
+    }
+
+    fn start_bounce(&mut self, bounce: u32) {
+        self.state.start_bounce(bounce);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let hash = self.state.take(1);
+        to_unit(mix_bits(hash ^ self.state.index as u64))
+    }
+}
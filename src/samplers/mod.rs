@@ -0,0 +1,200 @@
+//! Sample generators for the dimensions of a pixel sample
+//!
+//! A sample draws its dimensions in order: pixel jitter and lens, then the dimensions of each
+//! bounce. Every bounce starts at its own dimension when it can, so that a path vertex gets the
+//! same dimensions whatever the previous vertices consumed. Install a sampler with
+//! `rng::with_sampler` to make `rng::with` draw from it.
+
+pub mod halton;
+pub mod independent;
+pub mod sobol;
+pub mod stratified;
+
+pub use halton::Halton;
+pub use independent::Independent;
+pub use sobol::Sobol;
+pub use stratified::Stratified;
+
+/// Dimensions used by the pixel jitter and the lens.
+const CAMERA_DIMENSIONS: u32 = 4;
+/// Dimensions reserved for each bounce.
+const BOUNCE_DIMENSIONS: u32 = 16;
+
+pub trait Sampler {
+    /// Starts sample `index` of `pixel` in the sequence chosen by `seed`, rewinding to the
+    /// first dimension. The samples only depend on these arguments.
+    fn start(&mut self, seed: u64, pixel: [i32; 2], index: u32);
+    /// Skips to the dimensions of bounce `bounce`, 0 being the first hit from the camera.
+    fn start_bounce(&mut self, bounce: u32);
+    /// The next dimension of the current sample, in [0, 1).
+    fn next_1d(&mut self) -> f64;
+    /// The next two dimensions, stratified jointly when the sampler supports it.
+    fn next_2d(&mut self) -> [f64; 2] {
+        [self.next_1d(), self.next_1d()]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Basic {
+    Independent(Independent),
+    Stratified(Stratified),
+    Halton(Halton),
+    Sobol(Sobol),
+}
+
+impl Default for Basic {
+    fn default() -> Self {
+        Basic::Sobol(Sobol::new())
+    }
+}
+
+impl Basic {
+    #[inline]
+    pub fn as_dyn_mut(&mut self) -> &mut dyn Sampler {
+        match self {
+            Basic::Independent(independent) => independent,
+            Basic::Stratified(stratified) => stratified,
+            Basic::Halton(halton) => halton,
+            Basic::Sobol(sobol) => sobol,
+        }
+    }
+}
+
+impl Sampler for Basic {
+    fn start(&mut self, seed: u64, pixel: [i32; 2], index: u32) {
+        self.as_dyn_mut().start(seed, pixel, index)
+    }
+
+    fn start_bounce(&mut self, bounce: u32) {
+        self.as_dyn_mut().start_bounce(bounce)
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        self.as_dyn_mut().next_1d()
+    }
+
+    fn next_2d(&mut self) -> [f64; 2] {
+        self.as_dyn_mut().next_2d()
+    }
+}
+
+/// Position of a sample in the sequence, shared by the samplers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct State {
+    /// Hash of the seed and the pixel, decorrelating pixels from each other.
+    pixel: u64,
+    index: u32,
+    dimension: u32,
+}
+
+impl State {
+    fn start(&mut self, seed: u64, [x, y]: [i32; 2], index: u32) {
+        self.pixel = mix_bits(seed ^ mix_bits((x as u32 as u64) << 32 | y as u32 as u64));
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    /// Never goes back, in case the previous bounce used more than its share.
+    fn start_bounce(&mut self, bounce: u32) {
+        let dimension = CAMERA_DIMENSIONS.saturating_add(bounce.saturating_mul(BOUNCE_DIMENSIONS));
+        self.dimension = self.dimension.max(dimension);
+    }
+
+    /// A hash unique to the pixel and the next dimension, then advances by `n` dimensions.
+    fn take(&mut self, n: u32) -> u64 {
+        let hash = mix_bits(self.pixel ^ self.dimension as u64);
+        self.dimension += n;
+        hash
+    }
+}
+
+/// The splitmix64 finalizer.
+pub(crate) fn mix_bits(mut x: u64) -> u64 {
+    x ^= x >> 31;
+    x = x.wrapping_mul(0x7fb5d329728ea185);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x81dadef4bc2dd44d);
+    x ^= x >> 33;
+    x
+}
+
+/// Maps the 53 high bits of `x` to [0, 1).
+#[inline]
+fn to_unit(x: u64) -> f64 {
+    (x >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Element `i` of a random permutation of `0..l` chosen by `p` (Kensler, "Correlated
+/// Multi-Jittered Sampling").
+fn permutation_element(mut i: u32, l: u32, p: u32) -> u32 {
+    let mut w = l - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < l {
+            break;
+        }
+    }
+    i.wrapping_add(p) % l
+}
+
+#[test]
+fn test() {
+    let n = 64;
+    let samplers = [
+        Basic::Stratified(Stratified::new(n)),
+        Basic::Halton(Halton::new()),
+        Basic::Sobol(Sobol::new()),
+    ];
+    for mut sampler in samplers {
+        let mut xs = vec![];
+        let mut ys = vec![];
+        for i in 0..n {
+            sampler.start(0, [3, 5], i);
+            let [x, y] = sampler.next_2d();
+            xs.push(x);
+            ys.push(y);
+            let z = sampler.next_1d();
+            assert!((0.0..1.0).contains(&z));
+        }
+        // One sample per stratum of width 1/n. The stratified sampler only stratifies its
+        // 8 x 8 grid, and Halton's second dimension is in base 3.
+        let strata = |v: &[f64]| {
+            let mut strata: Vec<_> = v.iter().map(|x| (x * n as f64) as u32).collect();
+            strata.sort();
+            strata.dedup();
+            strata.len()
+        };
+        assert!(strata(&xs) == n as usize || matches!(sampler, Basic::Stratified(_)));
+        if matches!(sampler, Basic::Sobol(_)) {
+            assert_eq!(strata(&ys), n as usize);
+        }
+    }
+
+    for i in 0..100 {
+        let p = permutation_element(i, 100, 7);
+        assert!(p < 100);
+        assert!((0..i).all(|j| permutation_element(j, 100, 7) != p));
+    }
+}
@@ -0,0 +1,80 @@
+use super::{mix_bits, Sampler, State};
+
+/// The first two dimensions of the Sobol sequence, Owen scrambled and padded: every pair of
+/// dimensions gets its own scramble and shuffled order (Burley, "Practical Hash-based Owen
+/// Scrambling"). Best with a power of two samples per pixel.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sobol {
+    state: State,
+}
+
+impl Sobol {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn shuffled_index(&self, hash: u64) -> u32 {
+        nested_uniform_scramble(self.state.index, hash as u32)
+    }
+}
+
+impl Sampler for Sobol {
+    fn start(&mut self, seed: u64, pixel: [i32; 2], index: u32) {
+        self.state.start(seed, pixel, index);
+    }
+
+    fn start_bounce(&mut self, bounce: u32) {
+        self.state.start_bounce(bounce);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let hash = self.state.take(1);
+        let i = self.shuffled_index(hash);
+        to_unit(nested_uniform_scramble(
+            i.reverse_bits(),
+            (hash >> 32) as u32,
+        ))
+    }
+
+    fn next_2d(&mut self) -> [f64; 2] {
+        let hash = self.state.take(2);
+        let i = self.shuffled_index(hash);
+        let seed = mix_bits(hash);
+        [
+            to_unit(nested_uniform_scramble(i.reverse_bits(), seed as u32)),
+            to_unit(nested_uniform_scramble(sobol_1(i), (seed >> 32) as u32)),
+        ]
+    }
+}
+
+/// The second dimension of the Sobol sequence, the first being `i.reverse_bits()`.
+fn sobol_1(mut i: u32) -> u32 {
+    let mut v = 1 << 31;
+    let mut x = 0;
+    while i != 0 {
+        if i & 1 != 0 {
+            x ^= v;
+        }
+        i >>= 1;
+        v ^= v >> 1;
+    }
+    x
+}
+
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+#[inline]
+fn to_unit(x: u32) -> f64 {
+    x as f64 * (1.0 / (1u64 << 32) as f64)
+}
@@ -0,0 +1,53 @@
+use super::{mix_bits, permutation_element, to_unit, Sampler, State};
+
+/// Jittered stratification over `sample_per_pixel` strata in 1D, and a grid of about
+/// `sample_per_pixel` cells in 2D. Each dimension visits the strata in its own random order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stratified {
+    sample_per_pixel: u32,
+    state: State,
+}
+
+impl Stratified {
+    pub fn new(sample_per_pixel: u32) -> Self {
+        Stratified {
+            sample_per_pixel: sample_per_pixel.max(1),
+            state: State::default(),
+        }
+    }
+
+    fn jitter(&self, hash: u64) -> u64 {
+        mix_bits(hash ^ self.state.index as u64)
+    }
+}
+
+impl Sampler for Stratified {
+    fn start(&mut self, seed: u64, pixel: [i32; 2], index: u32) {
+        self.state.start(seed, pixel, index);
+    }
+
+    fn start_bounce(&mut self, bounce: u32) {
+        self.state.start_bounce(bounce);
+    }
+
+    fn next_1d(&mut self) -> f64 {
+        let n = self.sample_per_pixel;
+        let hash = self.state.take(1);
+        let stratum = permutation_element(self.state.index % n, n, hash as u32);
+        (stratum as f64 + to_unit(self.jitter(hash))) / n as f64
+    }
+
+    fn next_2d(&mut self) -> [f64; 2] {
+        let n = self.sample_per_pixel;
+        let nx = (n as f64).sqrt().ceil() as u32;
+        let ny = n.div_ceil(nx);
+        let hash = self.state.take(2);
+        let cell = permutation_element(self.state.index % n, nx * ny, hash as u32);
+        let jx = self.jitter(hash);
+        let jy = mix_bits(jx);
+        [
+            ((cell % nx) as f64 + to_unit(jx)) / nx as f64,
+            ((cell / nx) as f64 + to_unit(jy)) / ny as f64,
+        ]
+    }
+}
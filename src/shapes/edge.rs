@@ -35,6 +35,7 @@ impl Shape for Edge {
                     normal: Onb::from_w((a.2).normalize()),
                     uv: [0.0, 0.0],
                     front: true,
+                    object: 0,
                 });
             }
             if t0 < b.0 && b.0 < t1 && (0.0..1.0).contains(&b.1) {
@@ -44,6 +45,7 @@ impl Shape for Edge {
                     normal: Onb::from_w((-b.2).normalize()),
                     uv: [0.0, 0.0],
                     front: false,
+                    object: 0,
                 });
             }
         }
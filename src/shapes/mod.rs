@@ -14,6 +14,8 @@ pub struct HitRec {
     pub normal: Onb,
     pub uv: [f64; 2],
     pub front: bool,
+    /// Index of the object hit in its resolver, set by `Object`.
+    pub object: u32,
 }
 
 pub trait Shape {
@@ -29,6 +31,11 @@ pub trait Shape {
         let _ = origin;
         Vec3::new([1.0, 0.0, 0.0])
     }
+
+    /// Surface area, or 0 for shapes that `random` can't sample.
+    fn area(&self) -> f64 {
+        0.0
+    }
 }
 
 #[derive(Clone)]
@@ -36,6 +43,7 @@ pub enum Basic {
     Sphere(Sphere),
     Triangle(Triangle<false>),
     TriangleBothSide(Triangle<true>),
+    TriangleWithNormalsBothSide(triangle_with_normals::TriangleWithNormals<true>),
     Edge(edge::Edge),
 }
 
@@ -46,6 +54,7 @@ impl Basic {
             Basic::Sphere(sphere) => sphere,
             Basic::Triangle(triangle) => triangle,
             Basic::TriangleBothSide(triangle) => triangle,
+            Basic::TriangleWithNormalsBothSide(triangle) => triangle,
             Basic::Edge(edge) => edge,
         }
     }
@@ -67,4 +76,8 @@ impl Shape for Basic {
     fn random(&self, origin: &Vec3) -> Vec3 {
         self.as_ref().random(origin)
     }
+
+    fn area(&self) -> f64 {
+        self.as_ref().area()
+    }
 }
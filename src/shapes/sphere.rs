@@ -41,6 +41,7 @@ impl Shape for Sphere {
                         normal: Onb::from_w(normal), // TODO
                         uv: get_sphere_uv(*normal),
                         front: true,
+                        object: 0,
                     });
                 }
             }
@@ -55,6 +56,7 @@ impl Shape for Sphere {
                         normal: Onb::from_w(normal), // TODO
                         uv: get_sphere_uv(*normal),
                         front: false,
+                        object: 0,
                     });
                 }
             }
@@ -86,6 +88,10 @@ impl Shape for Sphere {
         let uvw = crate::onb::Onb::from_w(direction.normalize());
         uvw.local(random_to_sphere(self.radius, distance_squared))
     }
+
+    fn area(&self) -> f64 {
+        2.0 * std::f64::consts::TAU * self.radius.powi(2)
+    }
 }
 
 fn get_sphere_uv(p: Vec3) -> [f64; 2] {
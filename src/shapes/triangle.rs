@@ -44,6 +44,7 @@ impl<const BOTH_SIDE: bool> Shape for Triangle<BOTH_SIDE> {
                     ), // TODO
                     uv: [u, v],
                     front,
+                    object: 0,
                 });
             }
         }
@@ -67,13 +68,9 @@ impl<const BOTH_SIDE: bool> Shape for Triangle<BOTH_SIDE> {
 
     fn pdf_value(&self, ray: Ray) -> f64 {
         if let Some(hr) = self.hit(&ray, 0.001, f64::INFINITY) {
-            let area = 0.5
-                * (self.0[1] - self.0[0])
-                    .cross(&(self.0[2] - self.0[0]))
-                    .norm();
             let distance_squared = hr.time.powi(2) * ray.direction.norm_sqr();
             let cosine = (ray.direction.dot(&hr.normal.w())).abs() / ray.direction.norm();
-            distance_squared / (cosine.max(1e-8) * area)
+            distance_squared / (cosine.max(1e-8) * self.area())
         } else {
             0.0
         }
@@ -89,6 +86,12 @@ impl<const BOTH_SIDE: bool> Shape for Triangle<BOTH_SIDE> {
         };
         random_point - *origin
     }
+
+    fn area(&self) -> f64 {
+        0.5 * (self.0[1] - self.0[0])
+            .cross(&(self.0[2] - self.0[0]))
+            .norm()
+    }
 }
 
 pub fn triangle_norm(v0: &Vec3, v1: &Vec3, v2: &Vec3) -> NormVec3 {
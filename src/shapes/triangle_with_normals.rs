@@ -53,6 +53,7 @@ impl<const BOTH_SIDE: bool> Shape for TriangleWithNormals<BOTH_SIDE> {
                     normal: Onb::from_w(normal), // TODO
                     uv: [u, v],
                     front,
+                    object: 0,
                 });
             }
         }
@@ -94,13 +95,9 @@ impl<const BOTH_SIDE: bool> Shape for TriangleWithNormals<BOTH_SIDE> {
 
     fn pdf_value(&self, ray: Ray) -> f64 {
         if let Some(hr) = self.hit(&ray, 0.001, f64::INFINITY) {
-            let area = 0.5
-                * (self.vertexes[1] - self.vertexes[0])
-                    .cross(&(self.vertexes[2] - self.vertexes[0]))
-                    .norm();
             let distance_squared = hr.time.powi(2) * ray.direction.norm_sqr();
             let cosine = (ray.direction.dot(&hr.normal.w())).abs() / ray.direction.norm();
-            distance_squared / (cosine.max(1e-8) * area)
+            distance_squared / (cosine.max(1e-8) * self.area())
         } else {
             0.0
         }
@@ -120,4 +117,10 @@ impl<const BOTH_SIDE: bool> Shape for TriangleWithNormals<BOTH_SIDE> {
         };
         random_point - *origin
     }
+
+    fn area(&self) -> f64 {
+        0.5 * (self.vertexes[1] - self.vertexes[0])
+            .cross(&(self.vertexes[2] - self.vertexes[0]))
+            .norm()
+    }
 }
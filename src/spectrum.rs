@@ -0,0 +1,71 @@
+//! Spectral rendering: visible wavelengths and their color
+//!
+//! A spectral path carries a single wavelength, sampled uniformly, and its RGB radiance is
+//! weighted by the response of each channel to that wavelength. The weights average to one
+//! over the visible range, so materials that ignore the wavelength render as in RGB.
+
+use rand::Rng;
+
+use crate::{rng, vec3::Vec3};
+
+/// Visible range, in nanometers.
+pub const MIN_WAVELENGTH: f64 = 380.0;
+pub const MAX_WAVELENGTH: f64 = 780.0;
+
+/// Mean of `xyz_to_rgb(cie_xyz(λ))` over the visible range.
+const RGB_MEAN: [f64; 3] = [0.320907, 0.253872, 0.242624];
+
+pub fn sample_wavelength() -> f64 {
+    rng::with(|rng| rng.gen_range(MIN_WAVELENGTH..MAX_WAVELENGTH))
+}
+
+/// CIE 1931 color matching functions, by the multi-lobe fit of Wyman et al., "Simple
+/// Analytic Approximations to the CIE XYZ Color Matching Functions", 2013.
+pub fn cie_xyz(wavelength: f64) -> Vec3 {
+    let g = |mu: f64, sigma1: f64, sigma2: f64| {
+        let sigma = if wavelength < mu { sigma1 } else { sigma2 };
+        (-0.5 * ((wavelength - mu) / sigma).powi(2)).exp()
+    };
+    Vec3::new([
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    ])
+}
+
+/// Linear sRGB, D65 white point.
+pub fn xyz_to_rgb(xyz: Vec3) -> Vec3 {
+    let [x, y, z] = [xyz.x(), xyz.y(), xyz.z()];
+    Vec3::new([
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    ])
+}
+
+/// Weight of the RGB radiance of a path of `wavelength` sampled by `sample_wavelength`.
+/// Some channels are negative around the wavelengths sRGB can't show.
+pub fn rgb_weight(wavelength: f64) -> Vec3 {
+    let rgb = xyz_to_rgb(cie_xyz(wavelength));
+    Vec3::new([
+        rgb.r() / RGB_MEAN[0],
+        rgb.g() / RGB_MEAN[1],
+        rgb.b() / RGB_MEAN[2],
+    ])
+}
+
+#[test]
+fn test() {
+    let n = 10000;
+    let mut sum = Vec3::ZERO;
+    for i in 0..n {
+        let t = (i as f64 + 0.5) / n as f64;
+        sum = sum + rgb_weight(MIN_WAVELENGTH + t * (MAX_WAVELENGTH - MIN_WAVELENGTH));
+    }
+    assert!((sum / n as f64 - Vec3::new([1.0; 3])).norm() < 1e-4);
+
+    let red = rgb_weight(650.0);
+    assert!(red.r() > red.g() && red.r() > red.b());
+    let blue = rgb_weight(450.0);
+    assert!(blue.b() > blue.r() && blue.b() > blue.g());
+}
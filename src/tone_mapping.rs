@@ -0,0 +1,103 @@
+//! Tone mapping of HDR colors into the displayable range
+
+use crate::vec3::Vec3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    /// Clamps each channel to 1.
+    Clamp,
+    /// `L / (1 + L)` on luminance.
+    Reinhard,
+    /// Reinhard that maps the luminance `white` to 1.
+    ReinhardExtended { white: f64 },
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve.
+    AcesFilmic,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMapping {
+    /// Exposure compensation in stops, applied before the operator.
+    pub exposure: f64,
+    pub operator: Operator,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        ToneMapping::new(Operator::Clamp)
+    }
+}
+
+impl ToneMapping {
+    pub fn new(operator: Operator) -> Self {
+        ToneMapping {
+            exposure: 0.0,
+            operator,
+        }
+    }
+
+    pub fn with_exposure(self, exposure: f64) -> Self {
+        ToneMapping { exposure, ..self }
+    }
+
+    /// Maps a linear HDR color to a linear color in [0, 1].
+    pub fn apply(&self, color: Vec3) -> Vec3 {
+        let color = color * self.exposure.exp2();
+        let color = match self.operator {
+            Operator::Clamp => color,
+            Operator::Reinhard => scale_luminance(color, |l| l / (1.0 + l)),
+            Operator::ReinhardExtended { white } => {
+                scale_luminance(color, |l| l * (1.0 + l / white.powi(2)) / (1.0 + l))
+            }
+            Operator::AcesFilmic => {
+                let f = |x: f64| {
+                    (x * (2.51 * x + 0.03) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0)
+                };
+                // The fit expects the exposure used by the reference implementation.
+                let c = color * 0.6;
+                Vec3::new([f(c.r()), f(c.g()), f(c.b())])
+            }
+        };
+        Vec3::new([
+            color.r().clamp(0.0, 1.0),
+            color.g().clamp(0.0, 1.0),
+            color.b().clamp(0.0, 1.0),
+        ])
+    }
+}
+
+/// Rec. 709 relative luminance.
+pub fn luminance(color: Vec3) -> f64 {
+    0.2126 * color.r() + 0.7152 * color.g() + 0.0722 * color.b()
+}
+
+fn scale_luminance(color: Vec3, f: impl Fn(f64) -> f64) -> Vec3 {
+    let l = luminance(color);
+    if l <= 0.0 {
+        Vec3::ZERO
+    } else {
+        color * (f(l) / l)
+    }
+}
+
+#[test]
+fn test() {
+    let bright = Vec3::new([3.0, 1.5, 0.5]);
+    for operator in [
+        Operator::Reinhard,
+        Operator::ReinhardExtended { white: 4.0 },
+        Operator::AcesFilmic,
+    ] {
+        let tm = ToneMapping::new(operator);
+        let c = tm.apply(bright);
+        assert!(c.r() <= 1.0 && c.g() < c.r() && c.b() < c.g());
+        assert_eq!(tm.apply(Vec3::ZERO), Vec3::ZERO);
+    }
+    let tm = ToneMapping::new(Operator::ReinhardExtended { white: 4.0 });
+    assert!((luminance(tm.apply(Vec3::new([4.0; 3]))) - 1.0).abs() < 1e-9);
+    assert_eq!(
+        ToneMapping::default()
+            .with_exposure(1.0)
+            .apply(Vec3::new([0.25; 3])),
+        Vec3::new([0.5; 3])
+    );
+}
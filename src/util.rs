@@ -13,12 +13,30 @@ pub fn gamma_to_linear(v: Vec3, gamma_factor: f64) -> Vec3 {
     ])
 }
 
+/// The sRGB OETF.
+pub fn linear_to_srgb(v: Vec3) -> Vec3 {
+    let f = |x: f64| {
+        if x <= 0.0031308 {
+            12.92 * x
+        } else {
+            1.055 * x.powf(2.4f64.recip()) - 0.055
+        }
+    };
+    Vec3::new([f(v.x()), f(v.y()), f(v.z())])
+}
+
+pub fn srgb_to_linear(v: Vec3) -> Vec3 {
+    let f = |x: f64| {
+        if x <= 0.04045 {
+            x / 12.92
+        } else {
+            ((x + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    Vec3::new([f(v.x()), f(v.y()), f(v.z())])
+}
+
 #[inline]
 pub fn vec3_to_u64(v: Vec3) -> u64 {
-    use std::mem::transmute;
-    unsafe {
-        transmute::<f64, u64>(v[0])
-            ^ (transmute::<f64, u64>(v[1]) << 1)
-            ^ (transmute::<f64, u64>(v[2]) << 2)
-    }
+    v[0].to_bits() ^ (v[1].to_bits() << 1) ^ (v[2].to_bits() << 2)
 }